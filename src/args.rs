@@ -1,4 +1,14 @@
-use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use crate::case::CaseStyle;
+use crate::filesystem::ItemType;
+use crate::normalize::NormalizationForm;
+use crate::tasks::validate::ConflictPolicy;
+use crate::truncate::TruncatePolicy;
+use crate::validity::Platform;
 
 struct Argument {
   value: &'static str,
@@ -39,23 +49,445 @@ struct Arguments {
 //   }
 // }
 
-#[derive(Debug, Clone, PartialEq, ValueEnum)]
-enum ItemType {
-  File,
-  Directory,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+  /// human-readable lines and progress bar
+  Text,
+  /// JSON Lines, one `ReportRecord` per target on stdout
+  Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+  /// by name, numbers by value, e.g. "img9" before "img10"
+  Natural,
+  /// in the order of the directory
+  None,
 }
 
 #[derive(Parser, Debug)]
 #[command(name = "nae", author = "s.aran", version = "0.90")]
-struct Args {
-  src: String,
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// rename files by a name pattern
+  Rename(RenameCommand),
+  /// write the new names to a mapping file or a script, without renaming
+  Plan(PlanCommand),
+  /// rename files by a mapping file of old and new names
+  Apply(ApplyCommand),
+  /// rename files back to the names in a journal
+  Undo(UndoCommand),
+  /// edit the names in a text editor and rename the files
+  Edit(EditCommand),
+  /// list files in the order they are numbered
+  Sort(SortCommand),
+  /// report names not allowed on the platform
+  Check(CheckCommand),
+  /// print a shell completion script
+  Completions(CompletionsCommand),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RenameCommand {
+  #[command(flatten)]
+  pub select: SelectArgs,
+  #[command(flatten)]
+  pub name: NameArgs,
+  #[command(flatten)]
+  pub sort: SortArgs,
+  #[command(flatten)]
+  pub validate: ValidateArgs,
+  #[command(flatten)]
+  pub apply: ApplyArgs,
+  #[command(flatten)]
+  pub report: ReportArgs,
+  #[command(flatten)]
+  pub config: ConfigArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PlanCommand {
+  #[command(flatten)]
+  pub select: SelectArgs,
+  #[command(flatten)]
+  pub name: NameArgs,
+
+  /// file to write, a CSV/TSV mapping file to edit and `apply`,
+  /// or a shell script (PowerShell for .ps1) with a .undo script reversing it
+  #[arg(short = 'o', long, value_name = "FILE", required = true)]
+  pub output: Vec<PathBuf>,
+
+  #[command(flatten)]
+  pub sort: SortArgs,
+  #[command(flatten)]
+  pub validate: ValidateArgs,
+  #[command(flatten)]
+  pub report: ReportArgs,
+  #[command(flatten)]
+  pub config: ConfigArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ApplyCommand {
+  /// directory to rename files in
+  pub src: String,
+
+  /// CSV/TSV mapping file of old and new names (and ids)
+  pub mapping: PathBuf,
+
+  #[command(flatten)]
+  pub validate: ValidateArgs,
+  #[command(flatten)]
+  pub apply: ApplyArgs,
+  #[command(flatten)]
+  pub report: ReportArgs,
+  #[command(flatten)]
+  pub config: ConfigArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UndoCommand {
+  /// journal written by `--journal`
+  pub journal: PathBuf,
+
+  /// show the names to restore without renaming
+  #[arg(short = 'n', long)]
+  pub dry_run: bool,
+
+  #[command(flatten)]
+  pub report: ReportArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct EditCommand {
+  #[command(flatten)]
+  pub select: SelectArgs,
 
+  /// editor to run, with its arguments split at spaces (no quoting) [default: $VISUAL or $EDITOR]
+  #[arg(long)]
+  pub editor: Option<String>,
+
+  #[command(flatten)]
+  pub sort: SortArgs,
+  #[command(flatten)]
+  pub validate: ValidateArgs,
+  #[command(flatten)]
+  pub apply: ApplyArgs,
+  #[command(flatten)]
+  pub report: ReportArgs,
+  #[command(flatten)]
+  pub config: ConfigArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SortCommand {
+  #[command(flatten)]
+  pub select: SelectArgs,
+  #[command(flatten)]
+  pub sort: SortArgs,
+  #[command(flatten)]
+  pub config: ConfigArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CheckCommand {
+  #[command(flatten)]
+  pub select: SelectArgs,
+
+  /// check names against the platform [default: the current platform]
+  #[arg(long, value_enum)]
+  pub platform: Option<Platform>,
+
+  /// also report names differing only in case, for case-insensitive file systems (e.g. exFAT)
+  #[arg(long)]
+  pub ignore_case: bool,
+
+  #[command(flatten)]
+  pub report: ReportArgs,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CompletionsCommand {
+  pub shell: Shell,
+}
+
+///
+/// Files to rename.
+///
+#[derive(clap::Args, Debug)]
+pub struct SelectArgs {
+  /// directory to rename files in
+  pub src: String,
+
+  /// entries to rename: [f]iles, [d]irectories or [a]ll
   #[arg(short = 't', default_value = "f", value_enum)]
-  item_type: String,
+  pub item_type: ItemType,
+
+  /// enumerate files in subdirectories
+  #[arg(short = 'r', overrides_with = "no_recursive")]
+  pub recursive: bool,
+
+  /// do not enumerate files in subdirectories, overriding the config
+  #[arg(long, overrides_with = "recursive")]
+  pub no_recursive: bool,
+
+  /// rename only files whose name matches the regex
+  #[arg(long)]
+  pub filter: Option<String>,
+}
 
-  #[arg(short = 'r', default_value_t = 1)]
-  recursive: u8,
+///
+/// Order of the files, and so of the numbers of `?`.
+///
+#[derive(clap::Args, Debug)]
+pub struct SortArgs {
+  /// order of the files, and so of the numbers of `?`
+  #[arg(long, default_value = "natural", value_enum)]
+  pub sort: SortOrder,
+
+  /// sort ignoring case
+  #[arg(long, overrides_with = "no_sort_ignore_case")]
+  pub sort_ignore_case: bool,
+
+  /// sort respecting case, overriding the config
+  #[arg(long, overrides_with = "sort_ignore_case")]
+  pub no_sort_ignore_case: bool,
+
+  /// sort in descending order
+  #[arg(long, overrides_with = "no_reverse")]
+  pub reverse: bool,
+
+  /// sort in ascending order, overriding the config
+  #[arg(long, overrides_with = "reverse")]
+  pub no_reverse: bool,
+}
 
-  #[arg(short = 'v', default_value_t = 0)]
-  verbose: u8,
+///
+/// New names.
+///
+#[derive(clap::Args, Debug)]
+pub struct NameArgs {
+  /// new name pattern, e.g. "photo_???.jpg"
+  #[arg(default_value = "\\0")]
+  pub pattern: String,
+
+  /// first number of `?`
+  #[arg(long, default_value_t = 1)]
+  pub start: u32,
+
+  /// increment of `?`
+  #[arg(long, default_value_t = 1)]
+  pub step: u32,
+
+  /// replace characters not allowed on the platform instead of rejecting the names
+  #[arg(long, overrides_with = "no_sanitize")]
+  pub sanitize: bool,
+
+  /// reject names not allowed on the platform, overriding the config
+  #[arg(long, overrides_with = "sanitize")]
+  pub no_sanitize: bool,
+
+  /// character to replace invalid characters with
+  #[arg(long, default_value = "_")]
+  pub replacement: char,
+
+  /// shorten new names longer than the platform allows, keeping the extension and counter
+  #[arg(long, value_enum)]
+  pub truncate: Option<TruncatePolicy>,
+
+  /// apply substitutions from a rules file (.toml or .json) to new names
+  #[arg(long)]
+  pub rules: Option<PathBuf>,
+
+  /// convert new names to the case style, keeping the extension
+  #[arg(long, value_enum)]
+  pub case: Option<CaseStyle>,
+
+  /// normalize new names to the Unicode normalization form, e.g. "nfc" for names copied from macOS
+  #[arg(long, value_enum)]
+  pub normalize: Option<NormalizationForm>,
+
+  /// make new names safe for shells and URLs, e.g. "My Photo!.jpg" -> "My-Photo.jpg"
+  #[arg(long, overrides_with = "no_slug")]
+  pub slug: bool,
+
+  /// keep unsafe characters in new names, overriding the config
+  #[arg(long, overrides_with = "slug")]
+  pub no_slug: bool,
+
+  /// character to replace unsafe characters with in `--slug` and `\{0|slug}`
+  #[arg(long, default_value = "-")]
+  pub slug_replacement: char,
+
+  /// remove unsafe characters instead of replacing them
+  #[arg(long, conflicts_with = "slug_replacement", overrides_with = "no_slug_strip")]
+  pub slug_strip: bool,
+
+  /// replace unsafe characters instead of removing them, overriding the config
+  #[arg(long, overrides_with = "slug_strip")]
+  pub no_slug_strip: bool,
+
+  /// characters to keep even if they are unsafe, e.g. "+~"
+  #[arg(long, default_value = "")]
+  pub slug_allow: String,
+}
+
+///
+/// Checks of the new names.
+///
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+  /// detect conflicts ignoring case, for case-insensitive file systems (e.g. exFAT)
+  #[arg(long, overrides_with = "no_ignore_case")]
+  pub ignore_case: bool,
+
+  /// detect conflicts respecting case, overriding the config
+  #[arg(long, overrides_with = "ignore_case")]
+  pub no_ignore_case: bool,
+
+  /// reject new names not allowed on the platform [default: the current platform]
+  #[arg(long, value_enum)]
+  pub platform: Option<Platform>,
+
+  /// on conflicting new names, rename nothing or skip the conflicting files
+  #[arg(long, default_value = "fail", value_enum)]
+  pub on_conflict: ConflictPolicy,
+
+  /// find files with the same contents, and rename nothing or skip all but the first of them
+  #[arg(long, value_name = "POLICY", value_enum)]
+  pub duplicates: Option<ConflictPolicy>,
+}
+
+///
+/// Renaming.
+///
+#[derive(clap::Args, Debug)]
+pub struct ApplyArgs {
+  /// show new names without renaming
+  #[arg(short = 'n', long)]
+  pub dry_run: bool,
+
+  /// remove directories left empty by moving files out of them
+  #[arg(long, overrides_with = "no_remove_empty_dirs")]
+  pub remove_empty_dirs: bool,
+
+  /// keep directories left empty, overriding the config
+  #[arg(long, overrides_with = "remove_empty_dirs")]
+  pub no_remove_empty_dirs: bool,
+
+  /// compare checksums of the files copied to another file system
+  #[arg(long, overrides_with = "no_verify_checksum")]
+  pub verify_checksum: bool,
+
+  /// do not compare checksums of copied files, overriding the config
+  #[arg(long, overrides_with = "verify_checksum")]
+  pub no_verify_checksum: bool,
+
+  /// write renamed files to a JSON journal, to `undo` them
+  #[arg(long)]
+  pub journal: Option<PathBuf>,
+}
+
+///
+/// Output.
+///
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+  #[arg(long, default_value = "text", value_enum)]
+  pub format: OutputFormat,
+
+  #[arg(short = 'v', action = ArgAction::Count)]
+  pub verbose: u8,
+}
+
+///
+/// Config files.
+///
+#[derive(clap::Args, Debug)]
+pub struct ConfigArgs {
+  /// use the options of a preset in the config files
+  #[arg(long, value_name = "NAME")]
+  pub preset: Option<String>,
+
+  /// ignore the config files
+  #[arg(long)]
+  pub no_config: bool,
+}
+
+///
+/// Option groups of a command, each set if the command has it.
+///
+#[derive(Default)]
+pub struct ArgGroups<'a> {
+  pub select: Option<&'a mut SelectArgs>,
+  pub sort: Option<&'a mut SortArgs>,
+  pub name: Option<&'a mut NameArgs>,
+  pub validate: Option<&'a mut ValidateArgs>,
+  pub apply: Option<&'a mut ApplyArgs>,
+  pub report: Option<&'a mut ReportArgs>,
+}
+
+impl Command {
+  ///
+  /// Config options and the directory to find `.nae.toml` in, none if the command has no config.
+  ///
+  pub fn config(&self) -> Option<(&ConfigArgs, &str)> {
+    match self {
+      Command::Rename(c) => Some((&c.config, &c.select.src)),
+      Command::Plan(c) => Some((&c.config, &c.select.src)),
+      Command::Apply(c) => Some((&c.config, &c.src)),
+      Command::Edit(c) => Some((&c.config, &c.select.src)),
+      Command::Sort(c) => Some((&c.config, &c.select.src)),
+      Command::Undo(_) | Command::Check(_) | Command::Completions(_) => None,
+    }
+  }
+
+  ///
+  /// Option groups to set from the config files.
+  ///
+  pub fn groups(&mut self) -> ArgGroups<'_> {
+    match self {
+      Command::Rename(c) => ArgGroups {
+        select: Some(&mut c.select),
+        sort: Some(&mut c.sort),
+        name: Some(&mut c.name),
+        validate: Some(&mut c.validate),
+        apply: Some(&mut c.apply),
+        report: Some(&mut c.report),
+      },
+      Command::Plan(c) => ArgGroups {
+        select: Some(&mut c.select),
+        sort: Some(&mut c.sort),
+        name: Some(&mut c.name),
+        validate: Some(&mut c.validate),
+        report: Some(&mut c.report),
+        ..ArgGroups::default()
+      },
+      Command::Apply(c) => ArgGroups {
+        validate: Some(&mut c.validate),
+        apply: Some(&mut c.apply),
+        report: Some(&mut c.report),
+        ..ArgGroups::default()
+      },
+      Command::Edit(c) => ArgGroups {
+        select: Some(&mut c.select),
+        sort: Some(&mut c.sort),
+        validate: Some(&mut c.validate),
+        apply: Some(&mut c.apply),
+        report: Some(&mut c.report),
+        ..ArgGroups::default()
+      },
+      Command::Sort(c) => ArgGroups {
+        select: Some(&mut c.select),
+        sort: Some(&mut c.sort),
+        ..ArgGroups::default()
+      },
+      Command::Undo(_) | Command::Check(_) | Command::Completions(_) => ArgGroups::default(),
+    }
+  }
 }
@@ -0,0 +1,219 @@
+use clap::ValueEnum;
+
+use crate::extension::Extension;
+
+pub struct Case {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CaseStyle {
+  /// snake_case
+  Snake,
+  /// kebab-case
+  Kebab,
+  /// camelCase
+  Camel,
+  /// PascalCase
+  Pascal,
+  /// Title Case
+  Title,
+}
+
+impl CaseStyle {
+  ///
+  /// Find a case style by its modifier name.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::case::CaseStyle;
+  ///
+  /// assert_eq!(CaseStyle::from_name("snake"), Some(CaseStyle::Snake));
+  /// assert_eq!(CaseStyle::from_name("unknown"), None);
+  /// ```
+  ///
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "snake" => Some(CaseStyle::Snake),
+      "kebab" => Some(CaseStyle::Kebab),
+      "camel" => Some(CaseStyle::Camel),
+      "pascal" => Some(CaseStyle::Pascal),
+      "title" => Some(CaseStyle::Title),
+      _ => None,
+    }
+  }
+}
+
+impl Case {
+  ///
+  /// Convert a name to the case style, leaving the extension alone.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::case::{Case, CaseStyle};
+  ///
+  /// assert_eq!(Case::convert("myHTTPServer v2.RS", CaseStyle::Snake), "my_http_server_v_2.RS");
+  /// assert_eq!(Case::convert("user-profile_card.tsx", CaseStyle::Pascal), "UserProfileCard.tsx");
+  /// ```
+  ///
+  pub fn convert(name: &str, style: CaseStyle) -> String {
+    // hidden files stay hidden
+    let rest = name.trim_start_matches('.');
+    let (dots, name) = name.split_at(name.len() - rest.len());
+
+    let (stem, ext) = Extension::split(name);
+
+    let words = Case::words(stem);
+    let stem = match style {
+      CaseStyle::Snake => Case::join_lower(&words, "_"),
+      CaseStyle::Kebab => Case::join_lower(&words, "-"),
+      CaseStyle::Camel => words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| match i {
+          0 => w.to_lowercase(),
+          _ => Case::capitalize(w),
+        })
+        .collect(),
+      CaseStyle::Pascal => words.iter().map(|w| Case::capitalize(w)).collect(),
+      CaseStyle::Title => words
+        .iter()
+        .map(|w| Case::capitalize(w))
+        .collect::<Vec<String>>()
+        .join(" "),
+    };
+
+    format!("{}{}{}", dots, stem, ext)
+  }
+
+  ///
+  /// Split a name into words on spaces, underscores, dashes, dots,
+  /// camel case boundaries and digit/letter transitions.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::case::Case;
+  ///
+  /// assert_eq!(Case::words("parseJSONData_v2"), vec!["parse", "JSON", "Data", "v", "2"]);
+  /// ```
+  ///
+  pub fn words(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut ret: Vec<String> = vec![];
+    let mut word = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+      if c.is_whitespace() || c == '_' || c == '-' || c == '.' {
+        if !word.is_empty() {
+          ret.push(std::mem::take(&mut word));
+        }
+        continue;
+      }
+
+      if let Some(prev) = word.chars().last() {
+        let next = chars.get(i + 1).copied();
+        let boundary =
+          // "myFile"
+          (prev.is_lowercase() && c.is_uppercase())
+          // "HTTPServer"
+          || (prev.is_uppercase() && c.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+          // "v2", "2nd"
+          || (prev.is_numeric() != c.is_numeric());
+        if boundary {
+          ret.push(std::mem::take(&mut word));
+        }
+      }
+      word.push(c);
+    }
+
+    if !word.is_empty() {
+      ret.push(word);
+    }
+
+    ret
+  }
+
+  fn join_lower(words: &[String], separator: &str) -> String {
+    words
+      .iter()
+      .map(|w| w.to_lowercase())
+      .collect::<Vec<String>>()
+      .join(separator)
+  }
+
+  /// "hTTP" -> "Http", "ßa" -> "ßa" as "SSa" is not a title case
+  fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+      Some(first) => {
+        let mut upper = first.to_uppercase();
+        let first = match upper.len() {
+          1 => upper.next().unwrap(),
+          _ => first,
+        };
+        std::iter::once(first)
+          .chain(chars.as_str().to_lowercase().chars())
+          .collect()
+      }
+      None => String::new(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Case, CaseStyle};
+
+  #[test]
+  fn test_words_1() {
+    assert_eq!(vec!["my", "File"], Case::words("myFile"));
+    assert_eq!(vec!["HTTP", "Server"], Case::words("HTTPServer"));
+    assert_eq!(vec!["file", "10", "b"], Case::words("file10b"));
+    assert_eq!(vec!["a", "b", "c"], Case::words("  a__b--c  "));
+    assert_eq!(vec!["ねこ", "2"], Case::words("ねこ2"));
+    assert!(Case::words("").is_empty());
+  }
+
+  #[test]
+  fn test_convert_1() {
+    let name = "User Profile-card_v2.tsx";
+    assert_eq!(
+      "user_profile_card_v_2.tsx",
+      Case::convert(name, CaseStyle::Snake)
+    );
+    assert_eq!(
+      "user-profile-card-v-2.tsx",
+      Case::convert(name, CaseStyle::Kebab)
+    );
+    assert_eq!(
+      "userProfileCardV2.tsx",
+      Case::convert(name, CaseStyle::Camel)
+    );
+    assert_eq!(
+      "UserProfileCardV2.tsx",
+      Case::convert(name, CaseStyle::Pascal)
+    );
+    assert_eq!(
+      "User Profile Card V 2.tsx",
+      Case::convert(name, CaseStyle::Title)
+    );
+  }
+
+  #[test]
+  fn test_convert_extension_1() {
+    // no extension
+    assert_eq!("read_me", Case::convert("ReadMe", CaseStyle::Snake));
+    assert_eq!(".git_ignore", Case::convert(".gitIgnore", CaseStyle::Snake));
+    assert_eq!(".env.local", Case::convert(".Env.local", CaseStyle::Snake));
+    // not an extension
+    assert_eq!(
+      "vol_1_the_beginning",
+      Case::convert("Vol. 1 The Beginning", CaseStyle::Snake)
+    );
+  }
+
+  #[test]
+  fn test_convert_capitalize_1() {
+    // the uppercase of "ß" is "SS"
+    assert_eq!("ßa Straße", Case::convert("ßa straße", CaseStyle::Title));
+    assert_eq!("ßaÉcole", Case::convert("ßa école", CaseStyle::Pascal));
+  }
+}
@@ -0,0 +1,458 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use clap::{ArgMatches, ValueEnum, parser::ValueSource};
+use serde::Deserialize;
+
+use crate::args::ArgGroups;
+
+/// name of the config file in the directory to rename files in
+pub const DIR_CONFIG: &str = ".nae.toml";
+
+///
+/// Options stored in config files, named like the command line flags.
+/// Unset options are left to the next config file or the command line.
+///
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+  pub pattern: Option<String>,
+  /// "f", "d" or "a"
+  pub item_type: Option<String>,
+  pub recursive: Option<bool>,
+  pub filter: Option<String>,
+  /// "natural" or "none"
+  pub sort: Option<String>,
+  pub sort_ignore_case: Option<bool>,
+  pub reverse: Option<bool>,
+  pub start: Option<u32>,
+  pub step: Option<u32>,
+  /// "fail" or "skip"
+  pub on_conflict: Option<String>,
+  /// "fail" or "skip"
+  pub duplicates: Option<String>,
+  pub ignore_case: Option<bool>,
+  pub platform: Option<String>,
+  pub sanitize: Option<bool>,
+  pub replacement: Option<char>,
+  pub truncate: Option<String>,
+  /// relative to the directory of the config file
+  pub rules: Option<PathBuf>,
+  pub case: Option<String>,
+  pub normalize: Option<String>,
+  pub slug: Option<bool>,
+  pub slug_replacement: Option<char>,
+  pub slug_strip: Option<bool>,
+  pub slug_allow: Option<String>,
+  pub remove_empty_dirs: Option<bool>,
+  pub verify_checksum: Option<bool>,
+  pub format: Option<String>,
+}
+
+impl Options {
+  ///
+  /// Options of `other` over those of `self`.
+  ///
+  pub fn merge(self, other: Options) -> Options {
+    Options {
+      pattern: other.pattern.or(self.pattern),
+      item_type: other.item_type.or(self.item_type),
+      recursive: other.recursive.or(self.recursive),
+      filter: other.filter.or(self.filter),
+      sort: other.sort.or(self.sort),
+      sort_ignore_case: other.sort_ignore_case.or(self.sort_ignore_case),
+      reverse: other.reverse.or(self.reverse),
+      start: other.start.or(self.start),
+      step: other.step.or(self.step),
+      on_conflict: other.on_conflict.or(self.on_conflict),
+      duplicates: other.duplicates.or(self.duplicates),
+      ignore_case: other.ignore_case.or(self.ignore_case),
+      platform: other.platform.or(self.platform),
+      sanitize: other.sanitize.or(self.sanitize),
+      replacement: other.replacement.or(self.replacement),
+      truncate: other.truncate.or(self.truncate),
+      rules: other.rules.or(self.rules),
+      case: other.case.or(self.case),
+      normalize: other.normalize.or(self.normalize),
+      slug: other.slug.or(self.slug),
+      slug_replacement: other.slug_replacement.or(self.slug_replacement),
+      slug_strip: other.slug_strip.or(self.slug_strip),
+      slug_allow: other.slug_allow.or(self.slug_allow),
+      remove_empty_dirs: other.remove_empty_dirs.or(self.remove_empty_dirs),
+      verify_checksum: other.verify_checksum.or(self.verify_checksum),
+      format: other.format.or(self.format),
+    }
+  }
+
+  ///
+  /// Set the options to the option groups of a command, except those given on the command line.
+  ///
+  /// # Arguments
+  /// * `args` - option groups of the command parsed from `matches`
+  /// * `matches` - to find the arguments given on the command line
+  ///
+  pub fn apply(&self, args: ArgGroups, matches: &ArgMatches) -> Result<(), String> {
+    let cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    macro_rules! set {
+      ($args:ident . $field:ident) => {
+        if let Some(v) = self.$field.as_ref()
+          && !cli(stringify!($field))
+        {
+          $args.$field = v.clone();
+        }
+      };
+      // a bool flag is also given on the command line by its `--no-` negation
+      ($args:ident . $field:ident, bool) => {
+        if let Some(v) = self.$field.as_ref()
+          && !cli(stringify!($field))
+          && !cli(concat!("no_", stringify!($field)))
+        {
+          $args.$field = *v;
+        }
+      };
+      ($args:ident . $field:ident, Some) => {
+        if let Some(v) = self.$field.as_ref()
+          && !cli(stringify!($field))
+        {
+          $args.$field = Some(v.clone());
+        }
+      };
+      ($args:ident . $field:ident, enum) => {
+        if let Some(v) = self.$field.as_ref()
+          && !cli(stringify!($field))
+        {
+          $args.$field = Options::value(stringify!($field), v)?;
+        }
+      };
+      ($args:ident . $field:ident, Some enum) => {
+        if let Some(v) = self.$field.as_ref()
+          && !cli(stringify!($field))
+        {
+          $args.$field = Some(Options::value(stringify!($field), v)?);
+        }
+      };
+    }
+
+    if let Some(a) = args.select {
+      set!(a.item_type, enum);
+      set!(a.recursive, bool);
+      set!(a.filter, Some);
+    }
+    if let Some(a) = args.sort {
+      set!(a.sort, enum);
+      set!(a.sort_ignore_case, bool);
+      set!(a.reverse, bool);
+    }
+    if let Some(a) = args.name {
+      set!(a.pattern);
+      set!(a.start);
+      set!(a.step);
+      set!(a.sanitize, bool);
+      set!(a.replacement);
+      set!(a.truncate, Some enum);
+      set!(a.rules, Some);
+      set!(a.case, Some enum);
+      set!(a.normalize, Some enum);
+      set!(a.slug, bool);
+      set!(a.slug_replacement);
+      // a replacement on the command line overrides stripping in the config
+      if !cli("slug_replacement") {
+        set!(a.slug_strip, bool);
+      }
+      set!(a.slug_allow);
+    }
+    if let Some(a) = args.validate {
+      set!(a.on_conflict, enum);
+      set!(a.duplicates, Some enum);
+      set!(a.ignore_case, bool);
+      set!(a.platform, Some enum);
+    }
+    if let Some(a) = args.apply {
+      set!(a.remove_empty_dirs, bool);
+      set!(a.verify_checksum, bool);
+    }
+    if let Some(a) = args.report {
+      set!(a.format, enum);
+    }
+
+    Ok(())
+  }
+
+  /// value of an enum flag, e.g. "snake" for `case`
+  fn value<T: ValueEnum>(key: &str, s: &str) -> Result<T, String> {
+    T::from_str(s, true).map_err(|_| format!("{}: invalid value '{}'", key, s))
+  }
+
+  /// make `rules` relative to `dir`
+  fn resolve(&mut self, dir: &Path) {
+    if let Some(rules) = self.rules.as_mut()
+      && rules.is_relative()
+    {
+      *rules = dir.join(&rules);
+    }
+  }
+}
+
+///
+/// Default options and named presets, from `~/.config/nae/config.toml`
+/// and `.nae.toml` in the directory to rename files in.
+///
+/// Options are applied in this order, later ones winning:
+/// user defaults, directory defaults, the preset, the command line.
+///
+/// # Examples
+/// ```
+/// use nae::config::Config;
+///
+/// let config = Config::from_toml(r#"
+/// [defaults]
+/// recursive = true
+///
+/// [presets.photos]
+/// pattern = "photo_???.jpg"
+/// filter = '(?i)\.jpe?g$'
+/// start = 0
+/// "#).unwrap();
+/// let options = config.options(Some("photos")).unwrap();
+/// assert_eq!(options.pattern.as_deref(), Some("photo_???.jpg"));
+/// assert_eq!(options.recursive, Some(true));
+/// ```
+///
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub defaults: Options,
+  pub presets: BTreeMap<String, Options>,
+}
+
+impl Config {
+  pub fn from_toml(s: &str) -> Result<Self, String> {
+    toml::from_str::<Config>(s).map_err(|e| e.to_string())
+  }
+
+  ///
+  /// Load a config file, none if it does not exist.
+  ///
+  pub fn load(path: &Path) -> Result<Option<Self>, String> {
+    let s = match fs::read_to_string(path) {
+      Ok(s) => s,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    let mut config = Config::from_toml(&s).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    config.defaults.resolve(dir);
+    for preset in config.presets.values_mut() {
+      preset.resolve(dir);
+    }
+
+    Ok(Some(config))
+  }
+
+  ///
+  /// Load the user config and the config of `dir`.
+  ///
+  pub fn discover(dir: &Path) -> Result<Self, String> {
+    let mut ret = Config::default();
+    let paths = Config::user_path()
+      .into_iter()
+      .chain([dir.join(DIR_CONFIG)]);
+
+    for path in paths {
+      if let Some(config) = Config::load(&path)? {
+        ret = ret.merge(config);
+      }
+    }
+
+    Ok(ret)
+  }
+
+  ///
+  /// Path of the user config, e.g. "~/.config/nae/config.toml".
+  ///
+  pub fn user_path() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+      .filter(|d| !d.is_empty())
+      .map(PathBuf::from)
+      .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+
+    dir.map(|d| d.join("nae").join("config.toml"))
+  }
+
+  ///
+  /// Config of `other` over `self`, presets of the same name are replaced.
+  ///
+  pub fn merge(mut self, other: Config) -> Config {
+    self.presets.extend(other.presets);
+    Config {
+      defaults: self.defaults.merge(other.defaults),
+      presets: self.presets,
+    }
+  }
+
+  ///
+  /// Defaults with the preset over them.
+  ///
+  pub fn options(&self, preset: Option<&str>) -> Result<Options, String> {
+    let defaults = self.defaults.clone();
+    match preset {
+      Some(name) => match self.presets.get(name) {
+        Some(options) => Ok(defaults.merge(options.clone())),
+        None => Err(format!("unknown preset '{}'", name)),
+      },
+      None => Ok(defaults),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Config;
+  use crate::args::{Cli, Command, RenameCommand, SortOrder};
+  use crate::case::CaseStyle;
+  use crate::tasks::validate::ConflictPolicy;
+  use clap::{CommandFactory, FromArgMatches};
+  use std::{fs, path::Path};
+
+  fn parse(config: &Config, preset: Option<&str>, argv: &[&str]) -> Result<RenameCommand, String> {
+    let matches = Cli::command()
+      .try_get_matches_from(argv)
+      .map_err(|e| e.to_string())?;
+    let mut cli = Cli::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+    let (_, matches) = matches.subcommand().unwrap();
+    config
+      .options(preset)?
+      .apply(cli.command.groups(), matches)?;
+    match cli.command {
+      Command::Rename(c) => Ok(c),
+      _ => Err("not rename".to_string()),
+    }
+  }
+
+  #[test]
+  fn test_precedence_1() {
+    let user = Config::from_toml(
+      r#"
+[defaults]
+recursive = true
+case = "snake"
+start = 5
+
+[presets.photos]
+pattern = "photo_???.jpg"
+"#,
+    )
+    .unwrap();
+    let dir = Config::from_toml(
+      r#"
+[defaults]
+case = "kebab"
+
+[presets.photos]
+pattern = "img_???.jpg"
+on_conflict = "skip"
+sort = "none"
+"#,
+    )
+    .unwrap();
+    let config = user.merge(dir);
+
+    let args = parse(&config, None, &["nae", "rename", "."]).unwrap();
+    assert!(args.select.recursive);
+    assert_eq!(Some(CaseStyle::Kebab), args.name.case);
+    assert_eq!(5, args.name.start);
+    assert_eq!("\\0", args.name.pattern);
+
+    let args = parse(
+      &config,
+      Some("photos"),
+      &["nae", "rename", ".", "--case", "title", "--start", "1"],
+    )
+    .unwrap();
+    assert_eq!("img_???.jpg", args.name.pattern);
+    assert_eq!(ConflictPolicy::Skip, args.validate.on_conflict);
+    assert_eq!(SortOrder::None, args.sort.sort);
+    assert_eq!(Some(CaseStyle::Title), args.name.case);
+    assert_eq!(1, args.name.start);
+
+    let args = parse(&config, Some("photos"), &["nae", "rename", ".", "x_???"]).unwrap();
+    assert_eq!("x_???", args.name.pattern);
+  }
+
+  #[test]
+  fn test_negation_1() {
+    let config =
+      Config::from_toml("[defaults]\nrecursive = true\nslug = true\nremove_empty_dirs = true\n")
+        .unwrap();
+
+    let args = parse(
+      &config,
+      None,
+      &["nae", "rename", ".", "--no-recursive", "--no-slug"],
+    )
+    .unwrap();
+    assert!(!args.select.recursive);
+    assert!(!args.name.slug);
+    assert!(args.apply.remove_empty_dirs);
+
+    // the last of a flag and its negation wins
+    let args = parse(
+      &config,
+      None,
+      &[
+        "nae",
+        "rename",
+        ".",
+        "--no-remove-empty-dirs",
+        "--remove-empty-dirs",
+        "--no-recursive",
+        "-r",
+      ],
+    )
+    .unwrap();
+    assert!(args.apply.remove_empty_dirs);
+    assert!(args.select.recursive);
+  }
+
+  #[test]
+  fn test_invalid_1() {
+    let config = Config::from_toml("[defaults]\ncase = \"upper\"\n").unwrap();
+    assert_eq!(
+      "case: invalid value 'upper'",
+      parse(&config, None, &["nae", "rename", "."]).unwrap_err()
+    );
+    assert_eq!(
+      "unknown preset 'x'",
+      parse(&config, Some("x"), &["nae", "rename", "."]).unwrap_err()
+    );
+    assert!(Config::from_toml("[defaults]\nrecursiv = true\n").is_err());
+  }
+
+  #[test]
+  fn test_load_1() {
+    let dir = Path::new("test_config_1");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(
+      dir.join(".nae.toml"),
+      "[presets.docs]\nrules = \"rules.toml\"\nslug = true\n",
+    )
+    .unwrap();
+
+    let config = Config::load(&dir.join(".nae.toml"));
+    let missing = Config::load(&dir.join("missing.toml"));
+    fs::remove_dir_all(dir).unwrap();
+
+    let options = config.unwrap().unwrap().options(Some("docs")).unwrap();
+    assert_eq!(Some(dir.join("rules.toml")), options.rules);
+    assert_eq!(Some(true), options.slug);
+    assert!(missing.unwrap().is_none());
+  }
+}
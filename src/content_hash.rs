@@ -0,0 +1,145 @@
+use std::{fs::File, io::Read, path::Path};
+
+use sha2::Digest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+  Sha256,
+  Blake3,
+  Crc32,
+}
+
+impl HashAlgorithm {
+  ///
+  /// Find a hash algorithm by its name in patterns, e.g. "sha256".
+  ///
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "sha256" => Some(HashAlgorithm::Sha256),
+      "blake3" => Some(HashAlgorithm::Blake3),
+      "crc32" => Some(HashAlgorithm::Crc32),
+      _ => None,
+    }
+  }
+}
+
+/// state of a hash being computed
+enum Hasher {
+  Sha256(sha2::Sha256),
+  Blake3(Box<blake3::Hasher>),
+  Crc32(crc32fast::Hasher),
+}
+
+impl Hasher {
+  fn new(algorithm: HashAlgorithm) -> Self {
+    match algorithm {
+      HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+      HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+      HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+    }
+  }
+
+  fn update(&mut self, data: &[u8]) {
+    match self {
+      Hasher::Sha256(h) => h.update(data),
+      Hasher::Blake3(h) => {
+        h.update(data);
+      }
+      Hasher::Crc32(h) => h.update(data),
+    }
+  }
+
+  /// lowercase hex digest
+  fn finish(self) -> String {
+    match self {
+      Hasher::Sha256(h) => h.finalize().iter().map(|b| format!("{:02x}", b)).collect(),
+      Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+      Hasher::Crc32(h) => format!("{:08x}", h.finalize()),
+    }
+  }
+}
+
+///
+/// Hashes of file contents, as lowercase hex, for content-addressed names and finding duplicates.
+///
+/// # Examples
+/// ```
+/// use nae::content_hash::{ContentHash, HashAlgorithm};
+///
+/// assert_eq!(ContentHash::bytes(b"abc", HashAlgorithm::Crc32), "352441c2");
+/// assert_eq!(
+///   ContentHash::bytes(b"abc", HashAlgorithm::Sha256),
+///   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+/// );
+/// ```
+///
+pub struct ContentHash {}
+
+impl ContentHash {
+  pub fn bytes(data: &[u8], algorithm: HashAlgorithm) -> String {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(data);
+    hasher.finish()
+  }
+
+  ///
+  /// Hash the contents of a file, reading it in blocks so large files are not loaded in memory.
+  ///
+  pub fn file(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+      let n = file.read(&mut buf)?;
+      if n == 0 {
+        break;
+      }
+      hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finish())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ContentHash, HashAlgorithm};
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_file_1() {
+    let path = Path::new("test_content_hash_1.bin");
+    // more than one block
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(path, &data).unwrap();
+
+    let hashes: Vec<String> = [
+      HashAlgorithm::Sha256,
+      HashAlgorithm::Blake3,
+      HashAlgorithm::Crc32,
+    ]
+    .iter()
+    .map(|a| ContentHash::file(path, *a).unwrap())
+    .collect();
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(ContentHash::bytes(&data, HashAlgorithm::Sha256), hashes[0]);
+    assert_eq!(ContentHash::bytes(&data, HashAlgorithm::Blake3), hashes[1]);
+    assert_eq!(ContentHash::bytes(&data, HashAlgorithm::Crc32), hashes[2]);
+    assert_eq!(64, hashes[1].len());
+    assert!(ContentHash::file(Path::new("test_content_hash_none"), HashAlgorithm::Crc32).is_err());
+  }
+
+  #[test]
+  fn test_bytes_1() {
+    assert_eq!(
+      "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+      ContentHash::bytes(b"abc", HashAlgorithm::Blake3)
+    );
+    assert_eq!(
+      Some(HashAlgorithm::Blake3),
+      HashAlgorithm::from_name("blake3")
+    );
+    assert_eq!(None, HashAlgorithm::from_name("md5"));
+  }
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+///
+/// Event reported for each file while the rename pipeline runs.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameEvent {
+  /// file found by enumeration
+  Scanned { path: PathBuf },
+  /// new name computed
+  Planned { src: PathBuf, dest: PathBuf },
+  Renamed {
+    src: PathBuf,
+    dest: PathBuf,
+    /// time taken by the rename
+    elapsed: Duration,
+  },
+  /// not renamed, e.g. the new name is the same as the current one
+  Skipped { path: PathBuf, reason: String },
+  /// new name rejected before renaming, e.g. duplicated
+  Conflict {
+    path: PathBuf,
+    dest: Option<PathBuf>,
+    code: String,
+    message: String,
+  },
+  Failed {
+    path: PathBuf,
+    dest: Option<PathBuf>,
+    code: String,
+    message: String,
+    elapsed: Duration,
+  },
+}
+
+pub type EventCallback = Box<dyn FnMut(&RenameEvent)>;
+
+///
+/// Codes of `RenameEvent::Conflict` and `RenameEvent::Failed`, stable for machine-readable output.
+///
+pub mod code {
+  use std::io::ErrorKind;
+
+  pub const ALREADY_EXISTS: &str = "already_exists";
+  pub const DUPLICATED: &str = "duplicated";
+  /// new names only different in Unicode normalization, the same file on e.g. macOS
+  pub const NORMALIZATION_COLLISION: &str = "normalization_collision";
+  /// same contents as another file, e.g. found by `DuplicateTask`
+  pub const DUPLICATE_CONTENT: &str = "duplicate_content";
+  pub const NOT_COMPUTED: &str = "not_computed";
+  /// new name is outside the root directory, e.g. "../a.txt"
+  pub const OUTSIDE_ROOT: &str = "outside_root";
+  /// new name is not allowed on the target platform, e.g. "CON" on Windows
+  pub const INVALID_NAME: &str = "invalid_name";
+  /// new name is longer than the file system allows (`NAME_MAX`)
+  pub const NAME_TOO_LONG: &str = "name_too_long";
+  pub const NOT_FOUND: &str = "not_found";
+  pub const PERMISSION_DENIED: &str = "permission_denied";
+  /// other I/O errors
+  pub const IO: &str = "io";
+  /// stopped by `CancelToken`, e.g. Ctrl+C
+  pub const CANCELLED: &str = "cancelled";
+
+  pub fn from_io(kind: ErrorKind) -> &'static str {
+    match kind {
+      ErrorKind::AlreadyExists => ALREADY_EXISTS,
+      ErrorKind::NotFound => NOT_FOUND,
+      ErrorKind::PermissionDenied => PERMISSION_DENIED,
+      _ => IO,
+    }
+  }
+}
+
+///
+/// Token to stop the rename pipeline between files.
+/// Clones share the same state, so one can be handed to another thread or a signal handler.
+///
+/// # Examples
+/// ```
+/// use nae::event::CancelToken;
+///
+/// let token = CancelToken::new();
+/// let other = token.clone();
+/// assert!(!token.is_cancelled());
+///
+/// other.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+///
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+  flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn cancel(&self) {
+    self.flag.store(true, Ordering::SeqCst);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.flag.load(Ordering::SeqCst)
+  }
+
+  ///
+  /// Shared flag, e.g. for `signal_hook::flag::register()`.
+  ///
+  pub fn flag(&self) -> Arc<AtomicBool> {
+    self.flag.clone()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::CancelToken;
+  use std::sync::atomic::Ordering;
+
+  #[test]
+  fn test_cancel_1() {
+    let token = CancelToken::new();
+    let other = token.clone();
+
+    assert!(!token.is_cancelled());
+    other.cancel();
+    assert!(token.is_cancelled());
+    assert!(other.is_cancelled());
+  }
+
+  #[test]
+  fn test_flag_1() {
+    let token = CancelToken::new();
+    token.flag().store(true, Ordering::SeqCst);
+    assert!(token.is_cancelled());
+  }
+}
@@ -0,0 +1,48 @@
+pub struct Extension {}
+
+/// longer "extensions" are part of the name, e.g. "Vol. 1 The Beginning (Official Video)"
+const EXTENSION_MAX: usize = 16;
+
+impl Extension {
+  ///
+  /// Split a name into the stem and the extension, including its dot.
+  /// A leading dot (hidden files), a long or non-alphanumeric "extension" is part of the stem.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::extension::Extension;
+  ///
+  /// assert_eq!(Extension::split("a.tar.gz"), ("a.tar", ".gz"));
+  /// assert_eq!(Extension::split(".gitignore"), (".gitignore", ""));
+  /// assert_eq!(Extension::split("Vol. 1 The Beginning"), ("Vol. 1 The Beginning", ""));
+  /// ```
+  ///
+  pub fn split(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+      Some(i)
+        if i > 0
+          && name.len() - i <= EXTENSION_MAX
+          && name[i + 1..].chars().all(|c| c.is_alphanumeric()) =>
+      {
+        name.split_at(i)
+      }
+      _ => (name, ""),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Extension;
+
+  #[test]
+  fn test_split_1() {
+    assert_eq!(("photo", ".JPG"), Extension::split("photo.JPG"));
+    assert_eq!(("写真", ".ｊｐｇ"), Extension::split("写真.ｊｐｇ"));
+    assert_eq!(("a", ""), Extension::split("a"));
+    assert_eq!(("..", "."), Extension::split("..."));
+    // too long
+    let name = format!("a.{}", "b".repeat(16));
+    assert_eq!((name.as_str(), ""), Extension::split(&name));
+  }
+}
@@ -1,15 +1,57 @@
 use std::io::{Error, ErrorKind};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::content_hash::{ContentHash, HashAlgorithm};
 
 pub struct FileSystem {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ItemType {
+  #[default]
+  #[value(name = "f", alias = "file")]
+  File,
+  #[value(name = "d", alias = "directory")]
+  Directory,
+  /// files and directories
+  #[value(name = "a", alias = "all")]
+  All,
+}
+
+impl ItemType {
+  pub fn matches(&self, is_dir: bool) -> bool {
+    match self {
+      ItemType::File => !is_dir,
+      ItemType::Directory => is_dir,
+      ItemType::All => true,
+    }
+  }
+}
+
+/// check of the data copied by `FileSystem::copy_and_delete()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verify {
+  #[default]
+  Size,
+  /// size and checksum of the contents
+  Checksum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveMethod {
+  Renamed,
+  /// copied to another file system and the source removed
+  Copied,
+}
+
 impl FileSystem {
   pub fn rename(target: &Path, new_name: &str) -> std::io::Result<()> {
     if target.file_name() == None {
       return Err(Error::new(ErrorKind::InvalidInput, "Invalid filename"));
     }
 
-    match std::fs::rename(target, new_name) {
+    match FileSystem::move_path(target, Path::new(new_name), Verify::Size) {
       Ok(_) => Ok(()),
       Err(e) => {
         // println!("rename: {}", e);
@@ -18,6 +60,401 @@ impl FileSystem {
     }
   }
 
+  ///
+  /// Move a file or directory.
+  /// If `dest` is on another file system (e.g. `EXDEV`), falls back to `copy_and_delete()`.
+  /// A case-only change (e.g. "Photo.JPG" -> "photo.jpg") goes through a temporary name,
+  /// because `dest` is the same file as `src` on case-insensitive file systems.
+  ///
+  /// # Return
+  /// * `Ok(MoveMethod)` - how it was moved
+  ///
+  pub fn move_path(src: &Path, dest: &Path, verify: Verify) -> std::io::Result<MoveMethod> {
+    if FileSystem::is_case_only_change(src, dest) {
+      FileSystem::rename_via_temporary(src, dest)?;
+      return Ok(MoveMethod::Renamed);
+    }
+
+    match FileSystem::rename_noreplace(src, dest) {
+      Ok(()) => Ok(MoveMethod::Renamed),
+      Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+        FileSystem::copy_and_delete(src, dest, verify)?;
+        Ok(MoveMethod::Copied)
+      }
+      Err(e) => Err(e),
+    }
+  }
+
+  ///
+  /// Rename without overwriting an existing `dest`, atomically where the OS supports it.
+  /// Unlike checking `dest.exists()` first, another process cannot create `dest` in between.
+  ///
+  /// # Return
+  /// * `Err(e)` - `e.kind()` is `ErrorKind::AlreadyExists` if `dest` exists
+  ///
+  #[cfg(target_os = "linux")]
+  pub fn rename_noreplace(src: &Path, dest: &Path) -> std::io::Result<()> {
+    match FileSystem::renameat2(src, dest, libc::RENAME_NOREPLACE) {
+      // not supported by the file system (e.g. some network file systems) or the kernel
+      Err(e) if matches!(e.raw_os_error(), Some(libc::EINVAL) | Some(libc::ENOSYS)) => {
+        FileSystem::rename_checked(src, dest)
+      }
+      result => result,
+    }
+  }
+
+  #[cfg(target_os = "windows")]
+  pub fn rename_noreplace(src: &Path, dest: &Path) -> std::io::Result<()> {
+    use std::os::windows::prelude::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::MoveFileExW;
+
+    let wide = |p: &Path| -> Vec<u16> { p.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (src_w, dest_w) = (wide(src), wide(dest));
+
+    // without MOVEFILE_REPLACE_EXISTING, fails if `dest` exists
+    if unsafe { MoveFileExW(src_w.as_ptr(), dest_w.as_ptr(), 0) } == 0 {
+      return Err(Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+  pub fn rename_noreplace(src: &Path, dest: &Path) -> std::io::Result<()> {
+    FileSystem::rename_checked(src, dest)
+  }
+
+  ///
+  /// Returns true if `src` and `dest` differ only in case, e.g. "Photo.JPG" and "photo.jpg".
+  ///
+  /// # Examples
+  /// ```
+  /// use std::path::Path;
+  /// use nae::filesystem::FileSystem;
+  ///
+  /// assert!(FileSystem::is_case_only_change(Path::new("d/Photo.JPG"), Path::new("d/photo.jpg")));
+  /// assert!(!FileSystem::is_case_only_change(Path::new("d/a.jpg"), Path::new("d/a.jpg")));
+  /// ```
+  ///
+  pub fn is_case_only_change(src: &Path, dest: &Path) -> bool {
+    src != dest && FileSystem::case_fold(src) == FileSystem::case_fold(dest)
+  }
+
+  ///
+  /// Key to compare paths case-insensitively.
+  ///
+  pub fn case_fold(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+  }
+
+  ///
+  /// Returns true if both paths exist and refer to the same file,
+  /// e.g. "Photo.JPG" and "photo.jpg" on a case-insensitive file system.
+  ///
+  pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::MetadataExt;
+      match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+      }
+    }
+
+    #[cfg(windows)]
+    {
+      match (
+        FileSystem::get_id_by_filename(a),
+        FileSystem::get_id_by_filename(b),
+      ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+      }
+    }
+  }
+
+  /// rename through an unused name, so that a case-only change is never a no-op
+  fn rename_via_temporary(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let temporary = FileSystem::temporary_path(src);
+
+    FileSystem::rename_noreplace(src, &temporary)?;
+    if let Err(e) = FileSystem::rename_noreplace(&temporary, dest) {
+      // e.g. another file with the new name on a case-sensitive file system
+      let _ = std::fs::rename(&temporary, src);
+      return Err(e);
+    }
+
+    Ok(())
+  }
+
+  ///
+  /// Swap two files or directories, atomically where the OS supports it.
+  ///
+  #[cfg(target_os = "linux")]
+  pub fn exchange(a: &Path, b: &Path) -> std::io::Result<()> {
+    match FileSystem::renameat2(a, b, libc::RENAME_EXCHANGE) {
+      Err(e) if matches!(e.raw_os_error(), Some(libc::EINVAL) | Some(libc::ENOSYS)) => {
+        FileSystem::exchange_by_temporary(a, b)
+      }
+      result => result,
+    }
+  }
+
+  #[cfg(not(target_os = "linux"))]
+  pub fn exchange(a: &Path, b: &Path) -> std::io::Result<()> {
+    FileSystem::exchange_by_temporary(a, b)
+  }
+
+  #[cfg(target_os = "linux")]
+  fn renameat2(src: &Path, dest: &Path, flags: libc::c_uint) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let src_c = CString::new(src.as_os_str().as_bytes())?;
+    let dest_c = CString::new(dest.as_os_str().as_bytes())?;
+
+    let ret = unsafe {
+      libc::renameat2(
+        libc::AT_FDCWD,
+        src_c.as_ptr(),
+        libc::AT_FDCWD,
+        dest_c.as_ptr(),
+        flags,
+      )
+    };
+    if ret != 0 {
+      return Err(Error::last_os_error());
+    }
+    Ok(())
+  }
+
+  /// fallback of `rename_noreplace()`, racy
+  #[cfg(not(target_os = "windows"))]
+  fn rename_checked(src: &Path, dest: &Path) -> std::io::Result<()> {
+    if dest.symlink_metadata().is_ok() {
+      return Err(Error::new(
+        ErrorKind::AlreadyExists,
+        format!("{} already exists", dest.display()),
+      ));
+    }
+    std::fs::rename(src, dest)
+  }
+
+  /// fallback of `exchange()`, not atomic
+  fn exchange_by_temporary(a: &Path, b: &Path) -> std::io::Result<()> {
+    let temporary = FileSystem::temporary_path(a);
+
+    FileSystem::rename_noreplace(a, &temporary)?;
+    if let Err(e) = std::fs::rename(b, a) {
+      let _ = std::fs::rename(&temporary, a);
+      return Err(e);
+    }
+    if let Err(e) = std::fs::rename(&temporary, b) {
+      let _ = std::fs::rename(a, b);
+      let _ = std::fs::rename(&temporary, a);
+      return Err(e);
+    }
+
+    Ok(())
+  }
+
+  ///
+  /// Unused path in the same directory as `path`, e.g. for renaming through a temporary name.
+  ///
+  pub fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut i = std::process::id();
+
+    loop {
+      let temporary = path.with_file_name(format!(".{}.nae-{:x}", name, i));
+      if temporary.symlink_metadata().is_err() {
+        return temporary;
+      }
+      i = i.wrapping_add(1);
+    }
+  }
+
+  ///
+  /// Copy a file or directory tree, then remove the source.
+  /// Permissions, timestamps and extended attributes are preserved, and the copied data is verified.
+  /// If the copy fails, the partial copy is removed (only if created by this call) and the source is kept.
+  ///
+  /// # Arguments
+  /// * `src` - file or directory to move
+  /// * `dest` - new path, must not exist
+  /// * `verify` - check of the copied files
+  ///
+  pub fn copy_and_delete(src: &Path, dest: &Path, verify: Verify) -> std::io::Result<()> {
+    if dest.symlink_metadata().is_ok() {
+      return Err(Error::new(
+        ErrorKind::AlreadyExists,
+        format!("{} already exists", dest.display()),
+      ));
+    }
+
+    let meta = src.symlink_metadata()?;
+
+    // `dest` may be created by another process in the meantime, keep it then
+    let mut created = false;
+    if let Err(e) = FileSystem::copy_tree(src, dest, verify, &mut created) {
+      if !created {
+        return Err(e);
+      }
+      let _ = if meta.is_dir() {
+        std::fs::remove_dir_all(dest)
+      } else {
+        std::fs::remove_file(dest)
+      };
+      return Err(e);
+    }
+
+    if meta.is_dir() {
+      std::fs::remove_dir_all(src)
+    } else {
+      std::fs::remove_file(src)
+    }
+  }
+
+  /// copy `src` to `dest`, which must not exist, `created` is set once `dest` is created
+  fn copy_tree(
+    src: &Path,
+    dest: &Path,
+    verify: Verify,
+    created: &mut bool,
+  ) -> std::io::Result<()> {
+    let meta = src.symlink_metadata()?;
+
+    if meta.file_type().is_symlink() {
+      FileSystem::copy_symlink(src, dest)?;
+      *created = true;
+      return Ok(());
+    }
+
+    if meta.is_dir() {
+      std::fs::create_dir(dest)?;
+      *created = true;
+      for entry in src.read_dir()? {
+        let entry = entry?;
+        FileSystem::copy_tree(&entry.path(), &dest.join(entry.file_name()), verify, created)?;
+      }
+    } else {
+      let mut reader = std::fs::File::open(src)?;
+      let mut writer = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?;
+      *created = true;
+      std::io::copy(&mut reader, &mut writer)?;
+      drop(writer);
+
+      if dest.metadata()?.len() != meta.len() {
+        return Err(Error::other(format!("{}: size mismatch", dest.display())));
+      }
+      if verify == Verify::Checksum
+        && ContentHash::file(src, HashAlgorithm::Blake3)?
+          != ContentHash::file(dest, HashAlgorithm::Blake3)?
+      {
+        return Err(Error::other(format!(
+          "{}: checksum mismatch",
+          dest.display()
+        )));
+      }
+    }
+
+    // after the contents, a read-only directory cannot be written
+    FileSystem::copy_metadata(src, dest, &meta)
+  }
+
+  fn copy_metadata(src: &Path, dest: &Path, meta: &std::fs::Metadata) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+      // not supported by every file system
+      if let Ok(names) = xattr::list(src) {
+        for name in names {
+          if let Some(value) = xattr::get(src, &name)?
+            && let Err(e) = xattr::set(dest, &name, &value)
+            && e.kind() != ErrorKind::Unsupported
+          {
+            return Err(e);
+          }
+        }
+      }
+    }
+
+    let times = std::fs::FileTimes::new()
+      .set_accessed(meta.accessed()?)
+      .set_modified(meta.modified()?);
+    FileSystem::open_for_metadata(dest, meta.is_dir())?.set_times(times)?;
+
+    std::fs::set_permissions(dest, meta.permissions())
+  }
+
+  #[cfg(unix)]
+  fn open_for_metadata(path: &Path, _is_dir: bool) -> std::io::Result<std::fs::File> {
+    std::fs::File::open(path)
+  }
+
+  #[cfg(windows)]
+  fn open_for_metadata(path: &Path, is_dir: bool) -> std::io::Result<std::fs::File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if is_dir {
+      options.custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+    }
+    options.open(path)
+  }
+
+  #[cfg(unix)]
+  fn copy_symlink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(src)?, dest)
+  }
+
+  #[cfg(windows)]
+  fn copy_symlink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let target = std::fs::read_link(src)?;
+    if src.is_dir() {
+      std::os::windows::fs::symlink_dir(target, dest)
+    } else {
+      std::os::windows::fs::symlink_file(target, dest)
+    }
+  }
+
+  ///
+  /// Returns true if `dest` would be on the same file system as `src`, i.e. `std::fs::rename()` works.
+  /// `dest` does not need to exist, its nearest existing ancestor is checked.
+  ///
+  pub fn is_same_device(src: &Path, dest: &Path) -> std::io::Result<bool> {
+    let mut existing = dest;
+    while existing.symlink_metadata().is_err() {
+      existing = match existing.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+      };
+    }
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::MetadataExt;
+      Ok(src.symlink_metadata()?.dev() == existing.metadata()?.dev())
+    }
+
+    #[cfg(windows)]
+    {
+      // same drive or share
+      let prefix = |p: &Path| -> std::io::Result<Option<PathBuf>> {
+        Ok(
+          p.canonicalize()?
+            .components()
+            .next()
+            .map(|c| PathBuf::from(c.as_os_str())),
+        )
+      };
+      Ok(prefix(src)? == prefix(existing)?)
+    }
+  }
+
   ///
   /// Enumerate files in the target directory.
   /// If the target is a file, returns an error.
@@ -70,6 +507,151 @@ impl FileSystem {
     Ok(())
   }
 
+  ///
+  /// Enumerate files and/or directories in the target directory.
+  /// When recursing, the entries in a directory are passed before the directory itself,
+  /// so renaming them in this order never invalidates a path passed later.
+  ///
+  /// # Arguments
+  /// * `target` - The target directory.
+  /// * `recursive` - If true, enumerate entries recursively.
+  /// * `item_type` - Entries passed to the callback.
+  /// * `callback` - The callback function.
+  ///
+  /// # Examples
+  /// ```
+  /// use std::path::Path;
+  /// use nae::filesystem::{FileSystem, ItemType};
+  ///
+  /// let mut callback = |path: &Path| {
+  ///  println!("{}", path.display());    // e.g. "./src/tasks", after "./src/tasks/apply.rs"
+  /// };
+  ///
+  /// FileSystem::enum_entries(Path::new("./"), true, ItemType::All, &mut callback).unwrap();
+  /// ```
+  ///
+  pub fn enum_entries(
+    path: &Path,
+    recursive: bool,
+    item_type: ItemType,
+    callback: &mut dyn FnMut(&Path),
+  ) -> std::io::Result<()> {
+    if !path.is_dir() {
+      return Err(Error::new(ErrorKind::InvalidInput, "Invalid path"));
+    }
+
+    for dir_entry in path.read_dir()? {
+      let path = dir_entry?.path();
+      // symbolic links to directories are not followed
+      let is_dir = path.symlink_metadata()?.is_dir();
+
+      if recursive && is_dir {
+        FileSystem::enum_entries(&path, recursive, item_type, callback)?;
+      }
+
+      if item_type.matches(is_dir) {
+        callback(&path);
+      }
+    }
+
+    Ok(())
+  }
+
+  ///
+  /// Resolve "." and ".." in a path without accessing the file system.
+  /// A leading "." is kept, so the result can be compared with paths from `enum_entries()`.
+  ///
+  /// # Examples
+  /// ```
+  /// use std::path::Path;
+  /// use nae::filesystem::FileSystem;
+  ///
+  /// let path = FileSystem::normalize_path(Path::new("./photos/2024/../2023/./a.jpg"));
+  /// assert_eq!(path, Path::new("./photos/2023/a.jpg"));
+  /// ```
+  ///
+  pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+
+    for (i, c) in path.components().enumerate() {
+      match c {
+        Component::CurDir if i == 0 => ret.push(c),
+        Component::CurDir => {}
+        Component::ParentDir => match ret.components().next_back() {
+          Some(Component::Normal(_)) => {
+            ret.pop();
+          }
+          // "/.." is "/"
+          Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+          _ => ret.push(c),
+        },
+        _ => ret.push(c),
+      }
+    }
+
+    ret
+  }
+
+  ///
+  /// Returns true if `path` is `root` or inside it.
+  /// Symbolic links to directories are followed, so a link in `root` to another directory is outside.
+  /// The last component is not followed, a symbolic link is where it is.
+  ///
+  pub fn is_within(path: &Path, root: &Path) -> bool {
+    let strip = |p: &Path| -> PathBuf {
+      FileSystem::normalize_path(p)
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+    };
+    let normalized = strip(path);
+    match normalized.strip_prefix(strip(root)) {
+      Ok(rest) if rest.as_os_str().is_empty() => return true,
+      Ok(rest) if !rest.components().any(|c| c == Component::ParentDir) => {}
+      _ => return false,
+    }
+
+    // the nearest existing directory containing `path`
+    let mut dir = normalized.parent().unwrap_or(Path::new(""));
+    while !dir.as_os_str().is_empty() && dir.symlink_metadata().is_err() {
+      dir = dir.parent().unwrap_or(Path::new(""));
+    }
+    if dir.as_os_str().is_empty() {
+      dir = Path::new(".");
+    }
+
+    match (dir.canonicalize(), root.canonicalize()) {
+      (Ok(dir), Ok(root)) => dir.starts_with(root),
+      // not on the file system, as compared above
+      _ => true,
+    }
+  }
+
+  ///
+  /// Create the missing parent directories of `path`.
+  ///
+  /// # Return
+  /// * `Ok(Vec<PathBuf>)` - the directories created, outermost first
+  ///
+  pub fn create_parent_dirs(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut missing = vec![];
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+      if d.as_os_str().is_empty() || d.exists() {
+        break;
+      }
+      missing.push(d.to_path_buf());
+      dir = d.parent();
+    }
+
+    missing.reverse();
+    for d in &missing {
+      std::fs::create_dir(d)?;
+    }
+
+    Ok(missing)
+  }
+
   #[cfg(target_os = "windows")]
   pub fn get_id_by_filename(path: &Path) -> Result<String, String> {
     use std::{
@@ -80,8 +662,9 @@ impl FileSystem {
     use windows_sys::Win32::{
       Foundation::{CloseHandle, GetLastError, HANDLE, INVALID_HANDLE_VALUE},
       Storage::FileSystem::{
-        CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_GENERIC_READ, FILE_ID_128, FILE_ID_INFO,
-        FILE_SHARE_READ, FileIdInfo, GetFileInformationByHandleEx, OPEN_EXISTING,
+        CreateFileW, FILE_ATTRIBUTE_NORMAL, FILE_FLAG_BACKUP_SEMANTICS, FILE_GENERIC_READ,
+        FILE_ID_128, FILE_ID_INFO, FILE_SHARE_READ, FileIdInfo, GetFileInformationByHandleEx,
+        OPEN_EXISTING,
       },
     };
 
@@ -96,7 +679,8 @@ impl FileSystem {
         FILE_SHARE_READ,
         std::ptr::null(),
         OPEN_EXISTING,
-        FILE_ATTRIBUTE_NORMAL,
+        // needed to open directories
+        FILE_ATTRIBUTE_NORMAL | FILE_FLAG_BACKUP_SEMANTICS,
         std::ptr::null_mut(),
       )
     };
@@ -263,7 +847,7 @@ impl FileSystem {
     let inode = id_str.parse::<u64>().unwrap_or_default();
     let mut name = "".to_string();
 
-    let result = FileSystem::enum_files(hint_dir, true, &mut |p: &Path| {
+    let result = FileSystem::enum_entries(hint_dir, true, ItemType::All, &mut |p: &Path| {
       let meta = fs::metadata(p).unwrap();
       if meta.ino() == inode {
         name = p
@@ -399,6 +983,295 @@ mod tests {
     assert!(!called);
   }
 
+  #[test]
+  fn test_enum_entries_1() {
+    let dir = Path::new("test_enum_entries_1");
+    assert!(std::fs::create_dir_all(dir.join("sub")).is_ok());
+    assert!(create_file(&dir.join("a.txt")));
+    assert!(create_file(&dir.join("sub").join("b.txt")));
+
+    let mut all = vec![];
+    assert!(
+      FileSystem::enum_entries(dir, true, ItemType::All, &mut |p: &Path| all
+        .push(p.to_path_buf()))
+      .is_ok()
+    );
+    assert_eq!(3, all.len());
+    // children first
+    let sub = all.iter().position(|p| p == &dir.join("sub")).unwrap();
+    let b = all
+      .iter()
+      .position(|p| p == &dir.join("sub").join("b.txt"))
+      .unwrap();
+    assert!(b < sub);
+
+    let mut dirs = vec![];
+    assert!(
+      FileSystem::enum_entries(dir, true, ItemType::Directory, &mut |p: &Path| dirs
+        .push(p.to_path_buf()))
+      .is_ok()
+    );
+    assert_eq!(vec![dir.join("sub")], dirs);
+
+    let mut files = vec![];
+    assert!(
+      FileSystem::enum_entries(dir, false, ItemType::File, &mut |p: &Path| files
+        .push(p.to_path_buf()))
+      .is_ok()
+    );
+    assert_eq!(vec![dir.join("a.txt")], files);
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_normalize_path_1() {
+    assert_eq!(
+      Path::new("a/c"),
+      FileSystem::normalize_path(Path::new("a/b/../c"))
+    );
+    assert_eq!(
+      Path::new("../c"),
+      FileSystem::normalize_path(Path::new("a/../../c"))
+    );
+    assert_eq!(
+      Path::new("/c"),
+      FileSystem::normalize_path(Path::new("/../c"))
+    );
+    assert_eq!(
+      Path::new("./a"),
+      FileSystem::normalize_path(Path::new("./a/."))
+    );
+  }
+
+  #[test]
+  fn test_is_within_1() {
+    assert!(FileSystem::is_within(
+      Path::new("./root/2024/a.jpg"),
+      Path::new("root")
+    ));
+    assert!(FileSystem::is_within(Path::new("a.jpg"), Path::new(".")));
+    assert!(!FileSystem::is_within(
+      Path::new("root/../a.jpg"),
+      Path::new("root")
+    ));
+    assert!(!FileSystem::is_within(
+      Path::new("../a.jpg"),
+      Path::new(".")
+    ));
+    assert!(!FileSystem::is_within(
+      Path::new("/etc/a.jpg"),
+      Path::new("root")
+    ));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_is_within_symlink_1() {
+    let dir = Path::new("test_is_within_1");
+    let root = dir.join("root");
+    assert!(std::fs::create_dir_all(root.join("sub")).is_ok());
+    assert!(std::fs::create_dir_all(dir.join("outside")).is_ok());
+    assert!(std::os::unix::fs::symlink("../outside", root.join("link")).is_ok());
+
+    let r = (
+      FileSystem::is_within(&root.join("link").join("a.jpg"), &root),
+      FileSystem::is_within(&root.join("link").join("new").join("a.jpg"), &root),
+      FileSystem::is_within(&root.join("link"), &root),
+      FileSystem::is_within(&root.join("sub").join("new").join("a.jpg"), &root),
+      FileSystem::is_within(&root, &root),
+    );
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+
+    assert_eq!((false, false, true, true, true), r);
+  }
+
+  #[test]
+  fn test_create_parent_dirs_1() {
+    let dir = Path::new("test_create_parent_dirs_1");
+    let path = dir.join("2024").join("01").join("a.jpg");
+
+    let created = FileSystem::create_parent_dirs(&path).unwrap();
+    assert_eq!(
+      vec![
+        dir.to_path_buf(),
+        dir.join("2024"),
+        dir.join("2024").join("01")
+      ],
+      created
+    );
+    assert!(FileSystem::create_parent_dirs(&path).unwrap().is_empty());
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_copy_and_delete_1() {
+    let dir = Path::new("test_copy_and_delete_1");
+    let src = dir.join("src");
+    let dest = dir.join("dest");
+    assert!(std::fs::create_dir_all(src.join("sub")).is_ok());
+    assert!(std::fs::write(src.join("sub").join("a.txt"), "abc").is_ok());
+
+    let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    let file = std::fs::File::options()
+      .write(true)
+      .open(src.join("sub").join("a.txt"))
+      .unwrap();
+    assert!(file.set_modified(modified).is_ok());
+    let mut permissions = file.metadata().unwrap().permissions();
+    permissions.set_readonly(true);
+    assert!(file.set_permissions(permissions).is_ok());
+    drop(file);
+
+    assert!(FileSystem::copy_and_delete(&src, &dest, Verify::Checksum).is_ok());
+    assert!(!src.exists());
+
+    let copied = dest.join("sub").join("a.txt");
+    assert_eq!("abc", std::fs::read_to_string(&copied).unwrap());
+    let meta = copied.metadata().unwrap();
+    assert_eq!(modified, meta.modified().unwrap());
+    assert!(meta.permissions().readonly());
+
+    #[allow(clippy::permissions_set_readonly_false)]
+    {
+      let mut permissions = meta.permissions();
+      permissions.set_readonly(false);
+      assert!(std::fs::set_permissions(&copied, permissions).is_ok());
+    }
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_copy_and_delete_exists_1() {
+    let dir = Path::new("test_copy_and_delete_2");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(create_file(&dir.join("a.txt")));
+    assert!(create_file(&dir.join("b.txt")));
+
+    let e = FileSystem::copy_and_delete(&dir.join("a.txt"), &dir.join("b.txt"), Verify::Size);
+    assert_eq!(ErrorKind::AlreadyExists, e.unwrap_err().kind());
+    assert!(dir.join("a.txt").exists());
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_copy_tree_exists_1() {
+    let dir = Path::new("test_copy_and_delete_4");
+    assert!(std::fs::create_dir_all(dir.join("src")).is_ok());
+    assert!(create_file(&dir.join("src").join("a.txt")));
+    // created by another process after the check of `copy_and_delete()`
+    assert!(std::fs::create_dir_all(dir.join("dest")).is_ok());
+    assert!(create_file(&dir.join("a.txt")));
+
+    for (src, dest) in [("src", "dest"), ("src/a.txt", "a.txt")] {
+      let mut created = false;
+      let r = FileSystem::copy_tree(&dir.join(src), &dir.join(dest), Verify::Size, &mut created);
+      assert_eq!(ErrorKind::AlreadyExists, r.unwrap_err().kind());
+      assert!(!created);
+    }
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_copy_and_delete_symlink_1() {
+    let dir = Path::new("test_copy_and_delete_3");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(std::os::unix::fs::symlink("target.txt", dir.join("link")).is_ok());
+
+    assert!(
+      FileSystem::copy_and_delete(&dir.join("link"), &dir.join("moved"), Verify::Size).is_ok()
+    );
+    assert_eq!(
+      Path::new("target.txt"),
+      std::fs::read_link(dir.join("moved")).unwrap()
+    );
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_is_same_device_1() {
+    assert!(
+      FileSystem::is_same_device(Path::new("README.md"), Path::new("not_exists/a.md")).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_rename_noreplace_1() {
+    let dir = Path::new("test_rename_noreplace_1");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(std::fs::write(dir.join("a.txt"), "a").is_ok());
+    assert!(std::fs::write(dir.join("b.txt"), "b").is_ok());
+
+    let e = FileSystem::rename_noreplace(&dir.join("a.txt"), &dir.join("b.txt")).unwrap_err();
+    assert_eq!(ErrorKind::AlreadyExists, e.kind());
+    assert_eq!("b", std::fs::read_to_string(dir.join("b.txt")).unwrap());
+
+    assert!(FileSystem::rename_noreplace(&dir.join("a.txt"), &dir.join("c.txt")).is_ok());
+    assert_eq!("a", std::fs::read_to_string(dir.join("c.txt")).unwrap());
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_exchange_1() {
+    let dir = Path::new("test_exchange_1");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(std::fs::write(dir.join("a.txt"), "a").is_ok());
+    assert!(std::fs::write(dir.join("b.txt"), "b").is_ok());
+
+    assert!(FileSystem::exchange(&dir.join("a.txt"), &dir.join("b.txt")).is_ok());
+    assert_eq!("b", std::fs::read_to_string(dir.join("a.txt")).unwrap());
+    assert_eq!("a", std::fs::read_to_string(dir.join("b.txt")).unwrap());
+
+    assert!(FileSystem::exchange_by_temporary(&dir.join("a.txt"), &dir.join("b.txt")).is_ok());
+    assert_eq!("a", std::fs::read_to_string(dir.join("a.txt")).unwrap());
+    assert_eq!(2, std::fs::read_dir(dir).unwrap().count());
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_move_path_case_only_1() {
+    let dir = Path::new("test_move_path_case_only_1");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(std::fs::write(dir.join("Photo.JPG"), "a").is_ok());
+
+    let method =
+      FileSystem::move_path(&dir.join("Photo.JPG"), &dir.join("photo.jpg"), Verify::Size);
+    assert_eq!(MoveMethod::Renamed, method.unwrap());
+
+    let names: Vec<String> = std::fs::read_dir(dir)
+      .unwrap()
+      .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+      .collect();
+    assert_eq!(vec!["photo.jpg"], names);
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_move_path_case_only_2() {
+    // case-sensitive file system with both names
+    let dir = Path::new("test_move_path_case_only_2");
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(std::fs::write(dir.join("Photo.JPG"), "a").is_ok());
+    if std::fs::write(dir.join("photo.jpg"), "b").is_ok()
+      && !FileSystem::is_same_file(&dir.join("Photo.JPG"), &dir.join("photo.jpg"))
+    {
+      let e = FileSystem::move_path(&dir.join("Photo.JPG"), &dir.join("photo.jpg"), Verify::Size);
+      assert_eq!(ErrorKind::AlreadyExists, e.unwrap_err().kind());
+      assert_eq!("a", std::fs::read_to_string(dir.join("Photo.JPG")).unwrap());
+      assert_eq!(2, std::fs::read_dir(dir).unwrap().count());
+    }
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
   #[test]
   fn test_file_id() {
     let filename = "README.md";
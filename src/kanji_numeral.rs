@@ -0,0 +1,197 @@
+pub struct KanjiNumeral {}
+
+impl KanjiNumeral {
+  ///
+  /// Parse kanji numerals to a number.
+  /// Both the unit form (e.g. "二十三") and the positional form (e.g. "二〇二三") are accepted.
+  ///
+  /// # Arguments
+  /// * `s` - kanji numerals
+  ///
+  /// # Return
+  /// * `Option<u64>` - number, or None if `s` is not kanji numerals
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::kanji_numeral::KanjiNumeral;
+  ///
+  /// assert_eq!(KanjiNumeral::parse("十"), Some(10));
+  /// assert_eq!(KanjiNumeral::parse("二千二十三"), Some(2023));
+  /// assert_eq!(KanjiNumeral::parse("二〇二三"), Some(2023));
+  /// assert_eq!(KanjiNumeral::parse("ねこ"), None);
+  /// ```
+  ///
+  pub fn parse(s: &str) -> Option<u64> {
+    if s.is_empty() || KanjiNumeral::big_unit(s.chars().next().unwrap()).is_some() {
+      return None;
+    }
+
+    let has_unit = s
+      .chars()
+      .any(|c| KanjiNumeral::unit(c).is_some() || KanjiNumeral::big_unit(c).is_some());
+
+    if !has_unit {
+      // positional form
+      let mut ret: u64 = 0;
+      for c in s.chars() {
+        ret = ret.checked_mul(10)?.checked_add(KanjiNumeral::digit(c)?)?;
+      }
+      return Some(ret);
+    }
+
+    let mut total: u64 = 0;
+    let mut section: u64 = 0;
+    let mut current: Option<u64> = None;
+
+    for c in s.chars() {
+      if let Some(d) = KanjiNumeral::digit(c) {
+        // "二〇" inside the unit form, e.g. "千二〇"
+        current = Some(current.unwrap_or(0).checked_mul(10)?.checked_add(d)?);
+      } else if let Some(u) = KanjiNumeral::unit(c) {
+        section = section.checked_add(current.unwrap_or(1).checked_mul(u)?)?;
+        current = None;
+      } else if let Some(u) = KanjiNumeral::big_unit(c) {
+        section = section.checked_add(current.unwrap_or(0))?;
+        total = total.checked_add(section.checked_mul(u)?)?;
+        section = 0;
+        current = None;
+      } else {
+        return None;
+      }
+    }
+
+    total
+      .checked_add(section)?
+      .checked_add(current.unwrap_or(0))
+  }
+
+  ///
+  /// Replace runs of kanji numerals with half-width numbers.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::kanji_numeral::KanjiNumeral;
+  ///
+  /// assert_eq!(KanjiNumeral::replace_numerals("第十二話"), "第12話");
+  /// ```
+  ///
+  pub fn replace_numerals(s: &str) -> String {
+    let mut ret = String::new();
+    let mut run = String::new();
+
+    for c in s.chars() {
+      if KanjiNumeral::is_numeral(c) && !(run.is_empty() && KanjiNumeral::big_unit(c).is_some()) {
+        run.push(c);
+        continue;
+      }
+
+      KanjiNumeral::flush(&mut run, &mut ret);
+      ret.push(c);
+    }
+    KanjiNumeral::flush(&mut run, &mut ret);
+
+    ret
+  }
+
+  fn flush(run: &mut String, ret: &mut String) {
+    if run.is_empty() {
+      return;
+    }
+
+    match KanjiNumeral::parse(run) {
+      Some(n) => ret.push_str(&n.to_string()),
+      None => ret.push_str(run),
+    }
+    run.clear();
+  }
+
+  fn is_numeral(c: char) -> bool {
+    KanjiNumeral::digit(c).is_some()
+      || KanjiNumeral::unit(c).is_some()
+      || KanjiNumeral::big_unit(c).is_some()
+  }
+
+  fn digit(c: char) -> Option<u64> {
+    match c {
+      '〇' | '零' => Some(0),
+      '一' => Some(1),
+      '二' => Some(2),
+      '三' => Some(3),
+      '四' => Some(4),
+      '五' => Some(5),
+      '六' => Some(6),
+      '七' => Some(7),
+      '八' => Some(8),
+      '九' => Some(9),
+      _ => None,
+    }
+  }
+
+  fn unit(c: char) -> Option<u64> {
+    match c {
+      '十' => Some(10),
+      '百' => Some(100),
+      '千' => Some(1_000),
+      _ => None,
+    }
+  }
+
+  fn big_unit(c: char) -> Option<u64> {
+    match c {
+      '万' => Some(10_000),
+      '億' => Some(100_000_000),
+      '兆' => Some(1_000_000_000_000),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::KanjiNumeral;
+
+  #[test]
+  fn test_parse_1() {
+    assert_eq!(Some(0), KanjiNumeral::parse("〇"));
+    assert_eq!(Some(1), KanjiNumeral::parse("一"));
+    assert_eq!(Some(10), KanjiNumeral::parse("十"));
+    assert_eq!(Some(11), KanjiNumeral::parse("十一"));
+    assert_eq!(Some(20), KanjiNumeral::parse("二十"));
+    assert_eq!(Some(105), KanjiNumeral::parse("百五"));
+    assert_eq!(Some(1234), KanjiNumeral::parse("千二百三十四"));
+  }
+
+  #[test]
+  fn test_parse_2() {
+    assert_eq!(Some(10_000), KanjiNumeral::parse("一万"));
+    assert_eq!(Some(12_345), KanjiNumeral::parse("一万二千三百四十五"));
+    assert_eq!(Some(300_020_001), KanjiNumeral::parse("三億二万一"));
+  }
+
+  #[test]
+  fn test_parse_positional_1() {
+    assert_eq!(Some(2023), KanjiNumeral::parse("二〇二三"));
+    assert_eq!(Some(7), KanjiNumeral::parse("〇〇七"));
+  }
+
+  #[test]
+  fn test_parse_invalid_1() {
+    assert_eq!(None, KanjiNumeral::parse(""));
+    assert_eq!(None, KanjiNumeral::parse("万"));
+    assert_eq!(None, KanjiNumeral::parse("十a"));
+  }
+
+  #[test]
+  fn test_replace_numerals_1() {
+    let s = "第二話_第十話_第百一話.mp4";
+    let r = KanjiNumeral::replace_numerals(s);
+    assert_eq!(String::from("第2話_第10話_第101話.mp4"), r);
+  }
+
+  #[test]
+  fn test_replace_numerals_2() {
+    let s = "万歳三唱";
+    let r = KanjiNumeral::replace_numerals(s);
+    assert_eq!(String::from("万歳3唱"), r);
+  }
+}
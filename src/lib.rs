@@ -6,3 +6,22 @@ pub mod renamer;
 pub mod task;
 pub mod plan;
 pub mod args;
+pub mod normalize;
+pub mod transliterate;
+pub mod kanji_numeral;
+pub mod tasks;
+pub mod event;
+pub mod progress;
+pub mod report;
+pub mod preview;
+pub mod validity;
+pub mod slug;
+pub mod truncate;
+pub mod case;
+pub mod rules;
+pub mod mapping;
+pub mod script;
+pub mod config;
+pub mod content_hash;
+pub mod moves;
+pub mod extension;
@@ -1,52 +1,427 @@
-use std::env;
-use std::path;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::ExitCode;
+use std::rc::Rc;
 
-fn main() {
-  println!("Hello, world!");
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
 
-  for (i, a) in std::env::args().collect::<Vec<String>>().iter().enumerate() {
-    println!("{}: {}", i, a);
+use nae::args::{
+  ApplyArgs, Cli, Command, NameArgs, OutputFormat, SelectArgs, SortArgs, SortOrder, ValidateArgs,
+};
+use nae::config::Config;
+use nae::event::RenameEvent;
+use nae::filesystem::Verify;
+use nae::mapping::Mapping;
+use nae::natural_sort::NaturalSortOptions;
+use nae::plan::Plan;
+use nae::preview::Preview;
+use nae::progress::ProgressReporter;
+use nae::report::{ReportRecord, ReportStatus};
+use nae::rules::RuleSet;
+use nae::script::{Script, ScriptFormat};
+use nae::slug::SlugOptions;
+use nae::task::{Invoker, TaskError};
+use nae::tasks::{
+  apply::ApplyTask, compute::ComputeNamesTask, context::RenameContext, duplicate::DuplicateTask,
+  edit::EditTask, enumerate::EnumerateTask, filter::FilterTask, import::ImportTask,
+  journal::JournalTask, sort::SortTask, undo::UndoTask, validate::ValidateTask,
+};
+use nae::validity::Platform;
+
+fn main() -> ExitCode {
+  let matches = Cli::command().get_matches();
+  let Cli { mut command } = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+  if let Some((_, matches)) = matches.subcommand()
+    && let Err(e) = configure(&mut command, matches)
+  {
+    eprintln!("config: {}", e);
+    return ExitCode::FAILURE;
+  }
+
+  match &command {
+    Command::Completions(c) => {
+      clap_complete::generate(c.shell, &mut Cli::command(), "nae", &mut std::io::stdout());
+      ExitCode::SUCCESS
+    }
+    Command::Sort(c) => sort(&c.select, &c.sort),
+    _ => rename(&command),
+  }
+}
+
+/// run a command renaming files, or showing the new names
+fn rename(command: &Command) -> ExitCode {
+  let (root, report, dry_run) = match command {
+    Command::Rename(c) => (c.select.src.as_str(), &c.report, c.apply.dry_run),
+    // writing a plan only writes the new names
+    Command::Plan(c) => (c.select.src.as_str(), &c.report, true),
+    Command::Apply(c) => (c.src.as_str(), &c.report, c.apply.dry_run),
+    Command::Undo(c) => (".", &c.report, c.dry_run),
+    Command::Edit(c) => (c.select.src.as_str(), &c.report, c.apply.dry_run),
+    Command::Check(c) => (c.select.src.as_str(), &c.report, true),
+    Command::Sort(_) | Command::Completions(_) => unreachable!(),
+  };
+  // the names are checked, not renamed
+  let preview = !matches!(command, Command::Check(_));
+
+  let mut context = RenameContext::new(Path::new(root));
+
+  // Ctrl+C stops between files, the renamed files are kept in the journal; a second one exits at once
+  let flag = context.cancel.flag();
+  if let Err(e) =
+    signal_hook::flag::register_conditional_shutdown(signal_hook::consts::SIGINT, 130, flag.clone())
+      .and_then(|_| signal_hook::flag::register(signal_hook::consts::SIGINT, flag))
+  {
+    eprintln!("cannot handle Ctrl+C: {}", e);
+  }
+
+  let stderr = std::io::stderr();
+  let tty = stderr.is_terminal();
+  let reporter = Rc::new(RefCell::new(ProgressReporter::new(stderr, tty)));
+  reporter.borrow_mut().verbose = report.verbose > 0;
+
+  // new names rejected by validation, shown in the preview of a dry run
+  let conflicts = Rc::new(RefCell::new(HashMap::new()));
+  // written once the series is finished, to report what is left on disk
+  let records = Rc::new(RefCell::new(vec![]));
+
+  let r = reporter.clone();
+  let c = conflicts.clone();
+  let buffered = records.clone();
+  let format = report.format;
+  context.events = Some(Box::new(move |event: &RenameEvent| {
+    if let RenameEvent::Conflict { path, message, .. } = event {
+      c.borrow_mut().insert(path.clone(), message.clone());
+    }
+
+    match format {
+      OutputFormat::Text => {
+        if !(dry_run && preview && matches!(event, RenameEvent::Conflict { .. })) {
+          r.borrow_mut().report(event);
+        }
+      }
+      OutputFormat::Json => {
+        if let Some(record) = ReportRecord::from_event(event) {
+          buffered.borrow_mut().push(record);
+        }
+        // stdout is for the records, only the progress bar is drawn
+        if tty {
+          r.borrow_mut().report(event);
+        }
+      }
+    }
+  }));
+
+  let result = run(command, &mut context);
+  reporter.borrow_mut().finish();
+
+  if format == OutputFormat::Json {
+    let mut records = records.take();
+    if !dry_run {
+      // a failed series is rolled back, except undoing a journal
+      let rolled_back = result.is_err() && !matches!(command, Command::Undo(_));
+      ReportRecord::settle(
+        &mut records,
+        &context.entries,
+        rolled_back.then_some(&context.journal),
+      );
+    }
+    for record in records {
+      println!("{}", record.to_json_line());
+    }
+  }
+
+  if dry_run && preview && format == OutputFormat::Text {
+    let stdout = std::io::stdout();
+    let color = stdout.is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let mut preview = Preview::new(color);
+    let conflicts = conflicts.borrow();
+    for entry in &context.entries {
+      if let Some(dest) = entry.dest.as_ref() {
+        preview.add(
+          &entry.src,
+          dest,
+          conflicts.get(&entry.src).map(|c| c.as_str()),
+        );
+      }
+    }
+    print!("{}", preview.render());
+  }
+
+  if let Err(e) = result {
+    eprintln!("{}", e);
+    // 128 + SIGINT
+    return match context.cancel.is_cancelled() {
+      true => ExitCode::from(130),
+      false => ExitCode::FAILURE,
+    };
   }
 
-  /*
-  let (args, _) = opts! {
-      synopsis "file renamer program.";
-      auto_shorts  false;
-      // opt verbose:Option<String> = Some("".to_string()), desc:"be verbose.", short: 'v';
-      opt v:bool, desc: "be verbose.";
-      opt vv:bool, desc: "be verbose.";
-      opt vvv:bool, desc: "be verbose.";
-      opt n:bool=false, desc: "enable dry run mode.";
-      opt depth:i32=0, desc: "max directory depth.";
-      opt file_type:Option<String>, desc: "file type. set [d]irectory, [f]ile.", long: "type";
-      param pattern:String, desc: "filtering pattern.";
-      opt e:bool=false, desc: "enable regex mode for pattern parameter.";
-      param to:String, desc: "new file name";
-      opt version:bool=false, desc: "show version information.";
+  if let Command::Plan(c) = command
+    && let Err(e) = write_plan(&context, &c.output)
+  {
+    eprintln!("plan: {}", e);
+    return ExitCode::FAILURE;
   }
-  .parse_or_exit();
-  */
 
-  // let verbose = args.verbose.unwrap_or("".to_string());
-  // if verbose.len() > 0 {
-  //   println!("verbose: {}", verbose);
-  // }
+  if dry_run && preview && format == OutputFormat::Json {
+    for entry in &context.entries {
+      if let Some(dest) = entry.dest.as_ref() {
+        let record =
+          ReportRecord::new(entry.src.clone(), Some(dest.clone()), ReportStatus::Planned);
+        println!("{}", record.to_json_line());
+      }
+    }
+  }
+
+  if context.journal.cancelled {
+    eprintln!("cancelled: {} files renamed", context.journal.renamed.len());
+    // 128 + SIGINT
+    return ExitCode::from(130);
+  }
 
-  let target = path::PathBuf::from("./");
-  // enumFiles(&target);
+  ExitCode::SUCCESS
 }
 
-fn enum_files(target: &path::PathBuf) {
-  let files = target.read_dir().unwrap();
+/// list the files in the order they are numbered
+fn sort(select: &SelectArgs, sort: &SortArgs) -> ExitCode {
+  let mut context = RenameContext::new(Path::new(&select.src));
+  let result = {
+    let mut invoker = Invoker::new(&mut context);
+    add_select(&mut invoker, select).and_then(|_| {
+      add_sort(&mut invoker, sort);
+      invoker.execute_all()
+    })
+  };
+  if let Err(e) = result {
+    eprintln!("{}", e);
+    return ExitCode::FAILURE;
+  }
+
+  let width = context.entries.len().to_string().len();
+  for (i, entry) in context.entries.iter().enumerate() {
+    println!("{:>2$} {}", i + 1, entry.src.display(), width);
+  }
+
+  ExitCode::SUCCESS
+}
 
-  for dir_entry in files {
-    let dir_entry = dir_entry.unwrap();
-    let path = dir_entry.path();
+fn run(command: &Command, context: &mut RenameContext) -> Result<(), TaskError> {
+  let mut invoker = Invoker::new(context);
 
-    if path.is_dir() {
-      enum_files(&path);
-    } else {
-      println!("{}", path.to_str().unwrap());
+  let (validate, apply) = match command {
+    Command::Rename(c) => {
+      let platform = c.validate.platform.unwrap_or_else(Platform::current);
+      add_select(&mut invoker, &c.select)?;
+      add_sort(&mut invoker, &c.sort);
+      add_duplicate(&mut invoker, &c.validate);
+      invoker.add(compute_task(&c.name, platform)?);
+      (&c.validate, Some(&c.apply))
+    }
+    Command::Plan(c) => {
+      let platform = c.validate.platform.unwrap_or_else(Platform::current);
+      add_select(&mut invoker, &c.select)?;
+      add_sort(&mut invoker, &c.sort);
+      add_duplicate(&mut invoker, &c.validate);
+      invoker.add(compute_task(&c.name, platform)?);
+      (&c.validate, None)
+    }
+    Command::Apply(c) => {
+      invoker.add(ImportTask::new(&c.mapping));
+      add_duplicate(&mut invoker, &c.validate);
+      (&c.validate, Some(&c.apply))
+    }
+    Command::Edit(c) => {
+      add_select(&mut invoker, &c.select)?;
+      add_sort(&mut invoker, &c.sort);
+      add_duplicate(&mut invoker, &c.validate);
+      invoker.add(EditTask::new(&editor(c.editor.as_deref())));
+      (&c.validate, Some(&c.apply))
+    }
+    Command::Undo(c) => {
+      invoker.add(UndoTask::new(&c.journal, c.dry_run));
+      return invoker.execute_all();
     }
+    Command::Check(c) => {
+      add_select(&mut invoker, &c.select)?;
+      // the current names as new names
+      invoker.add(ComputeNamesTask::new("\\0"));
+      invoker.add(
+        ValidateTask::new()
+          .with_case_insensitive(c.ignore_case)
+          .with_platform(Some(c.platform.unwrap_or_else(Platform::current))),
+      );
+      return invoker.execute_all();
+    }
+    Command::Sort(_) | Command::Completions(_) => unreachable!(),
+  };
+
+  invoker.add(validate_task(validate));
+  if let Some(apply) = apply {
+    add_apply(&mut invoker, apply);
+  }
+
+  invoker.execute_all()
+}
+
+/// set the options of the config files and the preset not given on the command line
+fn configure(command: &mut Command, matches: &ArgMatches) -> Result<(), String> {
+  let Some((args, src)) = command.config() else {
+    return Ok(());
+  };
+
+  let config = if args.no_config {
+    Config::default()
+  } else {
+    Config::discover(Path::new(src))?
+  };
+  let options = config.options(args.preset.as_deref())?;
+  options.apply(command.groups(), matches)
+}
+
+fn add_select(invoker: &mut Invoker<RenameContext>, args: &SelectArgs) -> Result<(), TaskError> {
+  invoker.add(EnumerateTask::new(args.recursive, args.item_type));
+  if let Some(filter) = args.filter.as_ref() {
+    invoker.add(FilterTask::new(filter)?);
+  }
+  Ok(())
+}
+
+fn add_sort(invoker: &mut Invoker<RenameContext>, args: &SortArgs) {
+  if args.sort == SortOrder::Natural {
+    let options = NaturalSortOptions {
+      case_insensitive: args.sort_ignore_case,
+      ..NaturalSortOptions::default()
+    };
+    invoker.add(SortTask::new(options).with_reverse(args.reverse));
+  }
+}
+
+/// before computing the new names, so skipped files take no numbers
+fn add_duplicate(invoker: &mut Invoker<RenameContext>, args: &ValidateArgs) {
+  if let Some(policy) = args.duplicates {
+    invoker.add(DuplicateTask::new(policy));
+  }
+}
+
+fn validate_task(args: &ValidateArgs) -> ValidateTask {
+  ValidateTask::new()
+    .with_case_insensitive(args.ignore_case)
+    .with_platform(Some(args.platform.unwrap_or_else(Platform::current)))
+    .with_conflict_policy(args.on_conflict)
+}
+
+fn add_apply(invoker: &mut Invoker<RenameContext>, args: &ApplyArgs) {
+  let verify = if args.verify_checksum {
+    Verify::Checksum
+  } else {
+    Verify::Size
+  };
+  invoker.add(
+    ApplyTask::new(args.dry_run)
+      .with_remove_empty_dirs(args.remove_empty_dirs)
+      .with_verify(verify),
+  );
+  if let Some(journal) = args.journal.as_ref()
+    && !args.dry_run
+  {
+    invoker.add(JournalTask::new(journal));
+  }
+}
+
+fn compute_task(args: &NameArgs, platform: Platform) -> Result<ComputeNamesTask, TaskError> {
+  let slug_options = SlugOptions {
+    replacement: (!args.slug_strip).then_some(args.slug_replacement),
+    allow: args.slug_allow.clone(),
+  };
+  let rules = match args.rules.as_ref() {
+    Some(path) => Some(RuleSet::load(path).map_err(|e| TaskError::new("rules", &e))?),
+    None => None,
+  };
+
+  let mut compute = ComputeNamesTask::new(&args.pattern)
+    .with_counter(args.start, args.step)
+    .with_rules(rules)
+    .with_case(args.case)
+    .with_normalize(args.normalize)
+    .with_slug(args.slug)
+    .with_slug_options(slug_options);
+  if args.sanitize {
+    compute = compute.with_sanitize(platform, args.replacement);
+  }
+  if let Some(policy) = args.truncate {
+    compute = compute.with_truncate(platform, policy);
+  }
+
+  Ok(compute)
+}
+
+/// editor of `edit`, e.g. "vi"
+fn editor(editor: Option<&str>) -> String {
+  editor
+    .map(|e| e.to_string())
+    .or_else(|| std::env::var("VISUAL").ok())
+    .or_else(|| std::env::var("EDITOR").ok())
+    .filter(|e| !e.trim().is_empty())
+    .unwrap_or_else(|| {
+      if cfg!(windows) {
+        "notepad".to_string()
+      } else {
+        "vi".to_string()
+      }
+    })
+}
+
+/// write the plan of a dry run to each file, a mapping file or a script by the extension
+fn write_plan(context: &RenameContext, paths: &[std::path::PathBuf]) -> Result<(), String> {
+  let plan = Plan::from_entries(&context.root, &context.entries)?;
+  for path in paths {
+    let ext = path
+      .extension()
+      .map(|e| e.to_string_lossy().to_lowercase())
+      .unwrap_or_default();
+    match ext.as_str() {
+      "csv" | "tsv" => {
+        export(path, &plan)?;
+        eprintln!(
+          "exported: {} files to {}",
+          plan.get_plans().len(),
+          path.display()
+        );
+      }
+      "sh" | "ps1" => {
+        write_script(path, &plan)?;
+        eprintln!(
+          "exported: {} and {}",
+          path.display(),
+          Script::undo_path(path).display()
+        );
+      }
+      _ => {
+        return Err(format!(
+          "{}: unknown format, use .csv, .tsv, .sh or .ps1",
+          path.display()
+        ));
+      }
+    }
+  }
+  Ok(())
+}
+
+/// write the new names as a mapping file, to edit and `apply`
+fn export(path: &Path, plan: &Plan) -> Result<(), String> {
+  let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+  Mapping::write_plan(plan, file, Mapping::delimiter(path))
+}
+
+/// write a script doing the renames, and one undoing them
+fn write_script(path: &Path, plan: &Plan) -> Result<(), String> {
+  let format = ScriptFormat::from_path(path);
+  for (path, undo) in [(path.to_path_buf(), false), (Script::undo_path(path), true)] {
+    fs::write(&path, Script::render(plan, format, undo))
+      .map_err(|e| format!("{}: {}", path.display(), e))?;
   }
+  Ok(())
 }
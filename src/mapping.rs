@@ -0,0 +1,281 @@
+use std::{
+  fs,
+  io::{Read, Write},
+  path::Path,
+};
+
+use crate::filesystem::FileSystem;
+use crate::plan::Plan;
+
+///
+/// One row of a mapping file, old name to new name.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingRow {
+  /// line number in the file, for messages
+  pub line: usize,
+  pub src: String,
+  pub dest: String,
+  /// file id, to find files renamed after the mapping was written
+  pub id: Option<String>,
+}
+
+///
+/// Row not added to the plan.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unmatched {
+  pub line: usize,
+  pub src: String,
+  pub reason: String,
+}
+
+/// column names accepted in the header
+const SRC_COLUMNS: [&str; 4] = ["src", "source", "old", "from"];
+const DEST_COLUMNS: [&str; 4] = ["dest", "destination", "new", "to"];
+const ID_COLUMNS: [&str; 1] = ["id"];
+
+///
+/// Renames listed in CSV or TSV files, e.g. edited in a spreadsheet.
+///
+/// # Examples
+/// ```
+/// use nae::mapping::Mapping;
+///
+/// let rows = Mapping::parse("old,new\nIMG_0001.jpg,SKU-1234.jpg\n".as_bytes(), b',').unwrap();
+/// assert_eq!(rows[0].src, "IMG_0001.jpg");
+/// assert_eq!(rows[0].dest, "SKU-1234.jpg");
+/// ```
+///
+pub struct Mapping {}
+
+impl Mapping {
+  ///
+  /// Delimiter of the file by its extension, tab for ".tsv" and comma otherwise.
+  ///
+  pub fn delimiter(path: &Path) -> u8 {
+    match path.extension().and_then(|e| e.to_str()) {
+      Some(e) if e.eq_ignore_ascii_case("tsv") => b'\t',
+      _ => b',',
+    }
+  }
+
+  pub fn read(path: &Path) -> Result<Vec<MappingRow>, String> {
+    match fs::File::open(path) {
+      Ok(file) => Mapping::parse(file, Mapping::delimiter(path))
+        .map_err(|e| format!("{}: {}", path.display(), e)),
+      Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+  }
+
+  ///
+  /// Read rows of old and new names, and optionally ids.
+  /// Columns are found by the header (e.g. "old,new,id"),
+  /// or are the first three columns in that order if there is no header.
+  ///
+  pub fn parse<R: Read>(reader: R, delimiter: u8) -> Result<Vec<MappingRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+      .delimiter(delimiter)
+      .has_headers(false)
+      .flexible(true)
+      .trim(csv::Trim::All)
+      .from_reader(reader);
+
+    let mut records = vec![];
+    for record in reader.records() {
+      match record {
+        Ok(record) => records.push(record),
+        Err(e) => return Err(e.to_string()),
+      }
+    }
+
+    let mut columns = (0, 1, Some(2));
+    let mut skip = 0;
+    if let Some(header) = records.first() {
+      let find = |names: &[&str]| {
+        header
+          .iter()
+          .position(|h| names.iter().any(|n| n.eq_ignore_ascii_case(h)))
+      };
+      if let (Some(src), Some(dest)) = (find(&SRC_COLUMNS), find(&DEST_COLUMNS)) {
+        columns = (src, dest, find(&ID_COLUMNS));
+        skip = 1;
+      }
+    }
+
+    let mut ret = vec![];
+    for record in records.iter().skip(skip) {
+      let line = record.position().map(|p| p.line() as usize).unwrap_or(0);
+      let field = |i: usize| record.get(i).filter(|f| !f.is_empty());
+
+      let (src, dest) = match (field(columns.0), field(columns.1)) {
+        (Some(src), Some(dest)) => (src, dest),
+        (None, None) => continue,
+        _ => return Err(format!("line {}: old or new name is missing", line)),
+      };
+      ret.push(MappingRow {
+        line,
+        src: src.to_string(),
+        dest: dest.to_string(),
+        id: columns.2.and_then(field).map(|id| id.to_string()),
+      });
+    }
+
+    Ok(ret)
+  }
+
+  ///
+  /// Make a plan of the rows for the files in `dir`, old and new names are relative to `dir`.
+  /// A file not found by its old name is looked up by its id in the same directory if the row has one.
+  ///
+  /// # Return
+  /// * `(Plan, Vec<Unmatched>)` - plan, and the rows not added to it with the reasons
+  ///
+  pub fn to_plan(dir: &Path, rows: &[MappingRow]) -> (Plan, Vec<Unmatched>) {
+    let mut plan = Plan::new(dir);
+    let root = Path::new(plan.get_root().get_path()).to_path_buf();
+    let mut unmatched = vec![];
+
+    for row in rows {
+      let mut src = root.join(&row.src);
+
+      if let Some(id) = row.id.as_ref() {
+        if src.exists() {
+          if FileSystem::get_id_by_filename(&src).ok().as_ref() != Some(id) {
+            unmatched.push(Unmatched {
+              line: row.line,
+              src: row.src.clone(),
+              reason: "id mismatch".to_string(),
+            });
+            continue;
+          }
+        } else if let Some(found) = src.parent().and_then(|dir| Mapping::find_by_id(dir, id)) {
+          src = found;
+        }
+      }
+
+      if !src.exists() {
+        unmatched.push(Unmatched {
+          line: row.line,
+          src: row.src.clone(),
+          reason: "not found".to_string(),
+        });
+        continue;
+      }
+
+      if let Err(e) = plan.add(&src, &row.dest) {
+        unmatched.push(Unmatched {
+          line: row.line,
+          src: row.src.clone(),
+          reason: e,
+        });
+      }
+    }
+
+    (plan, unmatched)
+  }
+
+  ///
+  /// Write a plan as rows of "src,dest,id", with a header.
+  ///
+  pub fn write_plan<W: Write>(plan: &Plan, writer: W, delimiter: u8) -> Result<(), String> {
+    let mut writer = csv::WriterBuilder::new()
+      .delimiter(delimiter)
+      .from_writer(writer);
+
+    let mut write = || -> csv::Result<()> {
+      writer.write_record(["src", "dest", "id"])?;
+      for element in plan.get_plans() {
+        writer.write_record([
+          element.get_source(),
+          element.get_destination(),
+          element.get_id(),
+        ])?;
+      }
+      writer.flush()?;
+      Ok(())
+    };
+
+    write().map_err(|e| e.to_string())
+  }
+
+  /// file in `dir` (not in its subdirectories) with the id
+  fn find_by_id(dir: &Path, id: &str) -> Option<std::path::PathBuf> {
+    fs::read_dir(dir)
+      .ok()?
+      .filter_map(|e| e.ok())
+      .map(|e| e.path())
+      .find(|p| FileSystem::get_id_by_filename(p).ok().as_deref() == Some(id))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Mapping, MappingRow};
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_parse_1() {
+    let rows = Mapping::parse("a.jpg,b.jpg\n\"c, d.jpg\",e.jpg,123\n".as_bytes(), b',').unwrap();
+    assert_eq!(
+      vec![
+        MappingRow {
+          line: 1,
+          src: "a.jpg".to_string(),
+          dest: "b.jpg".to_string(),
+          id: None
+        },
+        MappingRow {
+          line: 2,
+          src: "c, d.jpg".to_string(),
+          dest: "e.jpg".to_string(),
+          id: Some("123".to_string())
+        },
+      ],
+      rows
+    );
+  }
+
+  #[test]
+  fn test_parse_header_1() {
+    let tsv = "SKU\tNew\tOld\n1234\tSKU-1234.jpg\tIMG_0001.jpg\n\n";
+    let rows = Mapping::parse(tsv.as_bytes(), b'\t').unwrap();
+    assert_eq!(1, rows.len());
+    assert_eq!(2, rows[0].line);
+    assert_eq!("IMG_0001.jpg", rows[0].src);
+    assert_eq!("SKU-1234.jpg", rows[0].dest);
+    assert_eq!(None, rows[0].id);
+
+    let e = Mapping::parse("old,new\na.jpg,\n".as_bytes(), b',').unwrap_err();
+    assert_eq!("line 2: old or new name is missing", e);
+  }
+
+  #[test]
+  fn test_to_plan_1() {
+    let dir = Path::new("test_mapping_1");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("a.jpg"), "a").unwrap();
+    fs::write(dir.join("b.jpg"), "b").unwrap();
+
+    let csv = "old,new,id\na.jpg,SKU-1.jpg,\nb.jpg,SKU-2.jpg,0\nc.jpg,SKU-3.jpg,\n";
+    let rows = Mapping::parse(csv.as_bytes(), b',').unwrap();
+    let (plan, unmatched) = Mapping::to_plan(dir, &rows);
+
+    let mut out = vec![];
+    Mapping::write_plan(&plan, &mut out, b',').unwrap();
+    fs::remove_dir_all(dir).unwrap();
+
+    assert_eq!(1, plan.get_plans().len());
+    assert_eq!("SKU-1.jpg", plan.get_plans()[0].get_destination());
+    assert_eq!(
+      vec![(3, "id mismatch"), (4, "not found")],
+      unmatched
+        .iter()
+        .map(|u| (u.line, u.reason.as_str()))
+        .collect::<Vec<(usize, &str)>>()
+    );
+
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("src,dest,id\na.jpg,SKU-1.jpg,"));
+  }
+}
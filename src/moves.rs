@@ -0,0 +1,129 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+///
+/// Order of moves so no file is moved onto another one still to be moved.
+///
+/// # Examples
+/// ```
+/// use nae::moves::Moves;
+///
+/// // "2" must be moved out of the way before "1"
+/// let moves = vec![("1", "2"), ("2", "3")];
+/// assert_eq!(Moves::order(moves, |_| unreachable!()), vec![("2", "3"), ("1", "2")]);
+/// ```
+///
+pub struct Moves {}
+
+impl Moves {
+  ///
+  /// Order moves, keeping their order where they do not depend on each other.
+  /// Cycles (e.g. a -> b, b -> a) are broken by moving one of the files aside first.
+  ///
+  /// # Arguments
+  /// * `moves` - pairs of source and destination, destinations must be unique
+  /// * `temporary` - temporary name of a file moved aside, e.g. `FileSystem::temporary_path`
+  ///
+  pub fn order<P, F>(mut moves: Vec<(P, P)>, mut temporary: F) -> Vec<(P, P)>
+  where
+    P: Clone + Eq + Hash,
+    F: FnMut(&P) -> P,
+  {
+    moves.retain(|(src, dest)| src != dest);
+    let count = moves.len();
+
+    // the move waiting for each one, whose destination is its source
+    let sources: HashMap<P, usize> = moves
+      .iter()
+      .enumerate()
+      .map(|(i, (src, _))| (src.clone(), i))
+      .collect();
+    let mut waiting: Vec<Option<usize>> = vec![None; count];
+    let mut ready = BinaryHeap::new();
+    for (i, (_, dest)) in moves.iter().enumerate() {
+      match sources.get(dest) {
+        Some(&j) => waiting[j] = Some(i),
+        None => ready.push(Reverse(i)),
+      }
+    }
+
+    let mut ret = Vec::with_capacity(count);
+    let mut done = vec![false; count];
+    let mut next = 0;
+    loop {
+      // the earliest move first, e.g. the contents of a directory before the directory
+      while let Some(Reverse(i)) = ready.pop() {
+        ret.push(moves[i].clone());
+        done[i] = true;
+        if let Some(w) = waiting[i].take() {
+          ready.push(Reverse(w));
+        }
+      }
+
+      while next < count && done[next] {
+        next += 1;
+      }
+      if next == count {
+        break;
+      }
+
+      // every move left waits for another one, move one aside
+      let tmp = temporary(&moves[next].0);
+      ret.push((moves[next].0.clone(), tmp.clone()));
+      moves[next].0 = tmp;
+      if let Some(w) = waiting[next].take() {
+        ready.push(Reverse(w));
+      }
+    }
+
+    ret
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Moves;
+
+  fn temporary(name: &&str) -> &'static str {
+    match *name {
+      "a" => "a~",
+      "1" => "1~",
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn test_order_chain_1() {
+    let moves = vec![("1", "2"), ("2", "3"), ("3", "4"), ("x", "x")];
+    assert_eq!(
+      vec![("3", "4"), ("2", "3"), ("1", "2")],
+      Moves::order(moves, temporary)
+    );
+  }
+
+  #[test]
+  fn test_order_cycle_1() {
+    let moves = vec![("a", "b"), ("b", "c"), ("c", "a"), ("d", "b~")];
+    assert_eq!(
+      vec![
+        ("d", "b~"),
+        ("a", "a~"),
+        ("c", "a"),
+        ("b", "c"),
+        ("a~", "b")
+      ],
+      Moves::order(moves, temporary)
+    );
+  }
+
+  #[test]
+  fn test_order_depth_1() {
+    // "d/a" is released by "d/b", and must still go before "d"
+    let moves = vec![("d/a", "d/b"), ("d/b", "d/c"), ("d", "e")];
+    assert_eq!(
+      vec![("d/b", "d/c"), ("d/a", "d/b"), ("d", "e")],
+      Moves::order(moves, temporary)
+    );
+  }
+}
@@ -1,12 +1,90 @@
 use std::cmp::Ordering;
 
+use crate::fullwidth::FullWidth;
+use crate::kanji_numeral::KanjiNumeral;
+use crate::transliterate::Transliterate;
+
 pub struct NaturalSort {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberMode {
+  /// "1.10" is version 1.10, greater than "1.9"
+  #[default]
+  Version,
+  /// "1.10" is decimal 1.1, less than "1.9"
+  Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NaturalSortOptions {
+  /// "A" and "a" are equal
+  pub case_insensitive: bool,
+  /// "é" and "e" are equal
+  pub ignore_accents: bool,
+  /// ignore leading English articles, e.g. "The Beatles" -> "Beatles"
+  pub ignore_articles: bool,
+  /// ignore leading punctuation, e.g. "(2) file" -> "2) file"
+  pub ignore_leading_punctuation: bool,
+  /// treat kanji numerals (e.g. "十二") as numbers
+  pub kanji_numerals: bool,
+  /// how to treat "1.10"
+  pub number_mode: NumberMode,
+  /// treat "-" followed by digits as negative numbers, e.g. "-5" < "-3" < "2"
+  pub signed: bool,
+}
+
+///
+/// Precomputed key for natural comparison.
+///
+/// # Examples
+/// ```
+/// use nae::natural_sort::{NaturalKey, NaturalSortOptions};
+///
+/// let options = NaturalSortOptions {
+///   case_insensitive: true,
+///   ..Default::default()
+/// };
+///
+/// let mut v = vec!["b10", "B9", "a"];
+/// v.sort_by_cached_key(|s| NaturalKey::new(s, &options));
+/// assert_eq!(v, vec!["a", "B9", "b10"]);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct NaturalKey {
+  parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+  Char(char),
+  Number(Number),
+}
+
+#[derive(Debug, Clone)]
+struct Number {
+  negative: bool,
+  /// integer part without leading zeros
+  integer: Vec<char>,
+  /// fractional part without trailing zeros (decimal mode only)
+  fraction: Vec<char>,
+  /// count of leading and trailing zeros, used only if everything else is equal
+  padding: usize,
+}
+
+/// articles ignored by `ignore_articles`
+const ARTICLES: [&str; 3] = ["the ", "a ", "an "];
+
 impl NaturalSort {
   /// internal common radix
   const RADIX: u32 = 10;
 
   /// strcmp with natural
+  ///
+  /// Full-width digits (e.g. "１０") are treated as numbers, too.
+  /// Numbers may have any number of digits.
+  /// Numbers with the same value (e.g. "7" and "007") are ordered by their leading zeros,
+  /// fewer first, only if the rest of the strings are equal.
   /// # Arguments
   /// * `a` - first string
   /// * `b` - second string
@@ -19,116 +97,265 @@ impl NaturalSort {
   /// assert_eq!(NaturalSort::strcmp_natural("1", "2"), Ordering::Less);
   /// assert_eq!(NaturalSort::strcmp_natural("2", "2"), Ordering::Equal);
   /// assert_eq!(NaturalSort::strcmp_natural("3", "2"), Ordering::Greater);
+  /// assert_eq!(NaturalSort::strcmp_natural("第２話", "第１０話"), Ordering::Less);
+  /// assert_eq!(NaturalSort::strcmp_natural("7", "007"), Ordering::Less);
   /// ```
   pub fn strcmp_natural(a: &str, b: &str) -> Ordering {
-    // println!("a: {} ({}), b: {} ({})", a, a.len(), b, b.len());
+    NaturalSort::strcmp_natural_with(a, b, &NaturalSortOptions::default())
+  }
+
+  /// strcmp with natural, also treating kanji numerals (e.g. "十二") as numbers
+  /// # Arguments
+  /// * `a` - first string
+  /// * `b` - second string
+  /// # Return
+  /// * `Ordering` - result of comparison
+  /// # Example
+  /// ```
+  /// use std::cmp::Ordering;
+  /// use nae::natural_sort::NaturalSort;
+  /// assert_eq!(NaturalSort::strcmp_natural_kanji("第二話", "第十話"), Ordering::Less);
+  /// ```
+  pub fn strcmp_natural_kanji(a: &str, b: &str) -> Ordering {
+    let options = NaturalSortOptions {
+      kanji_numerals: true,
+      ..Default::default()
+    };
+    NaturalSort::strcmp_natural_with(a, b, &options)
+  }
+
+  /// strcmp with natural, using the specified options
+  /// # Arguments
+  /// * `a` - first string
+  /// * `b` - second string
+  /// * `options` - collation options
+  /// # Return
+  /// * `Ordering` - result of comparison
+  /// # Example
+  /// ```
+  /// use std::cmp::Ordering;
+  /// use nae::natural_sort::{NaturalSort, NaturalSortOptions, NumberMode};
+  /// let options = NaturalSortOptions {
+  ///   number_mode: NumberMode::Decimal,
+  ///   ..Default::default()
+  /// };
+  /// assert_eq!(NaturalSort::strcmp_natural("1.10", "1.9"), Ordering::Greater);
+  /// assert_eq!(NaturalSort::strcmp_natural_with("1.10", "1.9", &options), Ordering::Less);
+  /// ```
+  pub fn strcmp_natural_with(a: &str, b: &str, options: &NaturalSortOptions) -> Ordering {
+    NaturalKey::new(a, options).cmp(&NaturalKey::new(b, options))
+  }
+
+  fn digits_end(s: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < s.len() && s[end].is_digit(NaturalSort::RADIX) {
+      end += 1;
+    }
+    end
+  }
+
+  pub fn natural_sort(v: &mut Vec<&str>) {
+    NaturalSort::natural_sort_with(v, &NaturalSortOptions::default());
+  }
 
-    if a == b {
-      // return Ordering::Equal;
+  pub fn natural_sort_kanji(v: &mut Vec<&str>) {
+    v.sort_unstable_by(|a, b| NaturalSort::strcmp_natural_kanji(a, b));
+  }
+
+  pub fn natural_sort_with(v: &mut Vec<&str>, options: &NaturalSortOptions) {
+    v.sort_by_cached_key(|s| NaturalKey::new(s, options));
+  }
+}
+
+impl NaturalKey {
+  ///
+  /// Create a key of the string for natural comparison.
+  ///
+  /// # Arguments
+  /// * `s` - string
+  /// * `options` - collation options
+  ///
+  pub fn new(s: &str, options: &NaturalSortOptions) -> Self {
+    let mut text = FullWidth::fullwidth_to_halfwidth_number(s);
+
+    if options.kanji_numerals {
+      text = KanjiNumeral::replace_numerals(&text);
+    }
+    if options.ignore_accents {
+      text = Transliterate::ascii_fold(&text);
+    }
+    if options.case_insensitive {
+      text = text.to_lowercase();
+    }
+    if options.ignore_leading_punctuation {
+      text = NaturalKey::trim_leading_punctuation(&text, options.signed).to_string();
+    }
+    if options.ignore_articles {
+      text = NaturalKey::trim_leading_article(&text).to_string();
     }
 
-    let a_len = a.len();
-    let b_len = b.len();
+    let chars: Vec<char> = text.chars().collect();
+    let mut parts = vec![];
+    let mut i = 0;
 
-    let max_len = std::cmp::max(a.len(), b.len());
-    // println!("max_len: {}", max_len);
+    while i < chars.len() {
+      let c = chars[i];
+      let is_digit_at = |n: usize| chars.get(n).is_some_and(|d| d.is_digit(NaturalSort::RADIX));
 
-    let mut ret = Ordering::Equal;
+      // "-5" is negative, but "file-5" is not
+      let negative = options.signed
+        && c == '-'
+        && is_digit_at(i + 1)
+        && (i == 0 || !chars[i - 1].is_alphanumeric());
 
-    for i in 0..max_len {
-      let ac = if i < a_len {
-        a.chars().nth(i).unwrap()
-      } else {
-        '\0'
-      };
-      let bc = if i < b_len {
-        b.chars().nth(i).unwrap()
-      } else {
-        '\0'
-      };
+      if !negative && !is_digit_at(i) {
+        parts.push(Part::Char(c));
+        i += 1;
+        continue;
+      }
 
-      // println!("a[{}]: {} (0x{:04X})", i, ac, ac as u32);
-      // println!("b[{}]: {} (0x{:04X})", i, bc, bc as u32);
-
-      if ac != bc {
-        ret = if ac < bc {
-          Ordering::Less
-        } else {
-          Ordering::Greater
-        };
-
-        // println!("a: {}, b: {}", ac.is_digit(Radix), bc.is_digit(Radix));
-        if ac.is_digit(NaturalSort::RADIX) && bc.is_digit(NaturalSort::RADIX) {
-          let mut ai = i;
-          for _ in i..a_len {
-            // println!("ai: {} -> {}", ai, a.chars().nth(ai).unwrap());
-            if !a.chars().nth(ai).unwrap().is_digit(NaturalSort::RADIX) {
-              break;
-            }
-            ai += 1;
-          }
+      let start = if negative { i + 1 } else { i };
+      let end = NaturalSort::digits_end(&chars, start);
+      let mut number = Number::new(negative, &chars[start..end]);
+      i = end;
+
+      if options.number_mode == NumberMode::Decimal
+        && chars.get(i) == Some(&'.')
+        && is_digit_at(i + 1)
+      {
+        let fraction_end = NaturalSort::digits_end(&chars, i + 1);
+        number.set_fraction(&chars[i + 1..fraction_end]);
+        i = fraction_end;
+      }
 
-          // println!("{}[{}..{}] --> {}", a, i, ai, &a[i..ai]);
-          let asl = &a[i..ai];
-          let anum = if asl == "" {
-            0
-          } else {
-            asl.parse::<u32>().unwrap()
-          };
-
-          let mut bi = i;
-          for _ in i..b_len {
-            // println!("bi: {} -> {}", bi, b.chars().nth(bi).unwrap());
-            if !b.chars().nth(bi).unwrap().is_digit(NaturalSort::RADIX) {
-              break;
-            }
-            bi += 1;
-          }
+      parts.push(Part::Number(number));
+    }
+
+    Self { parts }
+  }
+
+  fn trim_leading_punctuation(s: &str, signed: bool) -> &str {
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+      let next_is_digit = chars
+        .peek()
+        .is_some_and(|(_, d)| d.is_digit(NaturalSort::RADIX));
 
-          // println!("{}[{}..{}] --> {}", b, i, bi, &b[i..bi]);
-          let bsl = &b[i..bi];
-          let bnum = if bsl == "" {
-            0
-          } else {
-            bsl.parse::<u32>().unwrap()
-          };
-
-          // println!("anum: {}", anum);
-          // println!("bnum: {}", bnum);
-
-          ret = if anum < bnum {
-            Ordering::Less
-          } else {
-            Ordering::Greater
-          };
-          break;
-        } else if ret == Ordering::Equal {
-          continue;
-        } else {
-          break;
+      if c.is_alphanumeric() || (signed && c == '-' && next_is_digit) {
+        return &s[i..];
+      }
+    }
+
+    // nothing but punctuation
+    s
+  }
+
+  fn trim_leading_article(s: &str) -> &str {
+    for article in ARTICLES {
+      let matched = s
+        .get(..article.len())
+        .is_some_and(|p| p.eq_ignore_ascii_case(article));
+
+      if matched && s.len() > article.len() {
+        return s[article.len()..].trim_start();
+      }
+    }
+
+    s
+  }
+}
+
+impl Ord for NaturalKey {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // first difference of padding zeros, used only if everything else is equal
+    let mut tie_break = Ordering::Equal;
+
+    for (a, b) in self.parts.iter().zip(other.parts.iter()) {
+      let ret = match (a, b) {
+        (Part::Char(a), Part::Char(b)) => a.cmp(b),
+        (Part::Number(a), Part::Number(b)) => {
+          let ret = a.cmp_value(b);
+          if ret == Ordering::Equal && tie_break == Ordering::Equal {
+            tie_break = a.padding.cmp(&b.padding);
+          }
+          ret
         }
+        (Part::Char(a), Part::Number(b)) => a.cmp(&b.first_char()).then(Ordering::Less),
+        (Part::Number(a), Part::Char(b)) => a.first_char().cmp(b).then(Ordering::Greater),
+      };
+
+      if ret != Ordering::Equal {
+        return ret;
       }
     }
 
-    // println!(
-    //   "ret: {}",
-    //   match ret {
-    //     Ordering::Less => "Less",
-    //     Ordering::Greater => "Greater",
-    //     Ordering::Equal => "Equal",
-    //   }
-    // );
-    ret
+    // shorter one first, e.g. "a" < "a1"
+    self.parts.len().cmp(&other.parts.len()).then(tie_break)
+  }
+}
+
+impl PartialOrd for NaturalKey {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
   }
+}
 
-  pub fn natural_sort(v: &mut Vec<&str>) {
-    v.sort_unstable_by(|a, b| NaturalSort::strcmp_natural(&a, &b));
+impl PartialEq for NaturalKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for NaturalKey {}
+
+impl Number {
+  fn new(negative: bool, digits: &[char]) -> Self {
+    let zeros = digits.iter().take_while(|c| **c == '0').count();
+
+    Self {
+      negative,
+      integer: digits[zeros..].to_vec(),
+      fraction: vec![],
+      padding: zeros,
+    }
+  }
+
+  fn set_fraction(&mut self, digits: &[char]) {
+    let zeros = digits.iter().rev().take_while(|c| **c == '0').count();
+
+    self.fraction = digits[..digits.len() - zeros].to_vec();
+    self.padding += zeros;
+  }
+
+  /// representative character to compare with non-numeric characters
+  fn first_char(&self) -> char {
+    if self.negative { '-' } else { '0' }
+  }
+
+  fn cmp_value(&self, other: &Self) -> Ordering {
+    match (self.negative, other.negative) {
+      (false, false) => self.cmp_magnitude(other),
+      (true, true) => other.cmp_magnitude(self),
+      (true, false) => Ordering::Less,
+      (false, true) => Ordering::Greater,
+    }
+  }
+
+  fn cmp_magnitude(&self, other: &Self) -> Ordering {
+    // compare without parsing: a longer number is larger, otherwise digit by digit
+    self
+      .integer
+      .len()
+      .cmp(&other.integer.len())
+      .then_with(|| self.integer.cmp(&other.integer))
+      .then_with(|| self.fraction.cmp(&other.fraction))
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::natural_sort::NaturalSort;
+  use crate::natural_sort::{NaturalKey, NaturalSort, NaturalSortOptions, NumberMode};
   use std::cmp::Ordering;
 
   #[test]
@@ -319,4 +546,212 @@ mod tests {
     let result = NaturalSort::strcmp_natural(a, b);
     assert!(result == Ordering::Less);
   }
+
+  #[test]
+  fn test_natural_sort_fullwidth_1() {
+    let mut vec = vec!["第１０話", "第２話", "第4話", "第３話"];
+    let expected = vec!["第２話", "第３話", "第4話", "第１０話"];
+    NaturalSort::natural_sort(&mut vec);
+    assert_eq!(vec, expected);
+  }
+
+  #[test]
+  fn test_natural_sort_kanji_1() {
+    let mut vec = vec!["第十話", "第二話", "第十一話", "第三話"];
+    let expected = vec!["第二話", "第三話", "第十話", "第十一話"];
+    NaturalSort::natural_sort_kanji(&mut vec);
+    assert_eq!(vec, expected);
+  }
+
+  #[test]
+  fn test_strcmp_natural_kanji_1() {
+    assert_eq!(
+      NaturalSort::strcmp_natural_kanji("第二話", "第十話"),
+      Ordering::Less
+    );
+    assert_eq!(
+      NaturalSort::strcmp_natural_kanji("第２０話", "第十話"),
+      Ordering::Greater
+    );
+  }
+
+  #[test]
+  fn test_strcmp_natural_long_number_1() {
+    let a = "IMG_20231018123456.jpg";
+    let b = "IMG_20231018123457.jpg";
+
+    assert_eq!(NaturalSort::strcmp_natural(a, b), Ordering::Less);
+    assert_eq!(NaturalSort::strcmp_natural(b, a), Ordering::Greater);
+  }
+
+  #[test]
+  fn test_strcmp_natural_long_number_2() {
+    let a = "99999999999999999999999999999999";
+    let b = "100000000000000000000000000000000";
+
+    assert_eq!(NaturalSort::strcmp_natural(a, b), Ordering::Less);
+  }
+
+  #[test]
+  fn test_strcmp_natural_number_in_middle_1() {
+    let a = "a1b";
+    let b = "a10b";
+
+    assert_eq!(NaturalSort::strcmp_natural(a, b), Ordering::Less);
+    assert_eq!(NaturalSort::strcmp_natural(b, a), Ordering::Greater);
+  }
+
+  #[test]
+  fn test_strcmp_natural_leading_zeros_1() {
+    assert_eq!(NaturalSort::strcmp_natural("7", "007"), Ordering::Less);
+    assert_eq!(NaturalSort::strcmp_natural("007", "07"), Ordering::Greater);
+    assert_eq!(NaturalSort::strcmp_natural("0", "00"), Ordering::Less);
+    assert_eq!(NaturalSort::strcmp_natural("007", "007"), Ordering::Equal);
+  }
+
+  #[test]
+  fn test_strcmp_natural_leading_zeros_2() {
+    // leading zeros are only a tie-break
+    assert_eq!(NaturalSort::strcmp_natural("a007b", "a7c"), Ordering::Less);
+    assert_eq!(NaturalSort::strcmp_natural("a07", "a8"), Ordering::Less);
+    assert_eq!(
+      NaturalSort::strcmp_natural("a007_1", "a7_2"),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn test_strcmp_natural_multibyte_1() {
+    assert_eq!(
+      NaturalSort::strcmp_natural("ねこ9", "ねこ10"),
+      Ordering::Less
+    );
+    assert_eq!(
+      NaturalSort::strcmp_natural("ねこ10いぬ", "ねこ10ねこ"),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn test_natural_sort_many_1() {
+    let names = (0..50_000)
+      .rev()
+      .map(|i| format!("DSC_{}_20231018{:06}.JPG", i, i))
+      .collect::<Vec<String>>();
+    let mut vec = names.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+
+    NaturalSort::natural_sort(&mut vec);
+
+    assert_eq!(vec[0], "DSC_0_20231018000000.JPG");
+    assert_eq!(vec[10], "DSC_10_20231018000010.JPG");
+    assert_eq!(vec[49_999], "DSC_49999_20231018049999.JPG");
+  }
+
+  #[test]
+  fn test_strcmp_natural_case_insensitive_1() {
+    let options = NaturalSortOptions {
+      case_insensitive: true,
+      ..Default::default()
+    };
+
+    assert_eq!(NaturalSort::strcmp_natural("B", "a"), Ordering::Less);
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("B", "a", &options),
+      Ordering::Greater
+    );
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("File10", "file9", &options),
+      Ordering::Greater
+    );
+  }
+
+  #[test]
+  fn test_strcmp_natural_ignore_accents_1() {
+    let options = NaturalSortOptions {
+      ignore_accents: true,
+      ..Default::default()
+    };
+
+    assert_eq!(NaturalSort::strcmp_natural("é", "f"), Ordering::Greater);
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("é", "f", &options),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn test_natural_sort_ignore_articles_1() {
+    let options = NaturalSortOptions {
+      case_insensitive: true,
+      ignore_articles: true,
+      ignore_leading_punctuation: true,
+      ..Default::default()
+    };
+
+    let mut vec = vec!["The Zombies", "(Bee)", "an apple", "Beatles", "The"];
+    let expected = vec!["an apple", "Beatles", "(Bee)", "The", "The Zombies"];
+    NaturalSort::natural_sort_with(&mut vec, &options);
+    assert_eq!(vec, expected);
+  }
+
+  #[test]
+  fn test_natural_sort_decimal_1() {
+    let options = NaturalSortOptions {
+      number_mode: NumberMode::Decimal,
+      ..Default::default()
+    };
+
+    let mut vec = vec!["v1.10", "v1.9", "v1.2", "v2.0"];
+    NaturalSort::natural_sort(&mut vec);
+    assert_eq!(vec, vec!["v1.2", "v1.9", "v1.10", "v2.0"]);
+
+    NaturalSort::natural_sort_with(&mut vec, &options);
+    assert_eq!(vec, vec!["v1.10", "v1.2", "v1.9", "v2.0"]);
+  }
+
+  #[test]
+  fn test_strcmp_natural_decimal_1() {
+    let options = NaturalSortOptions {
+      number_mode: NumberMode::Decimal,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("1.5", "1.55", &options),
+      Ordering::Less
+    );
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("1.5", "1.50", &options),
+      Ordering::Less
+    );
+    assert_eq!(
+      NaturalSort::strcmp_natural_with("1.50x", "1.5y", &options),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn test_natural_sort_signed_1() {
+    let options = NaturalSortOptions {
+      signed: true,
+      ..Default::default()
+    };
+
+    let mut vec = vec!["2", "-3", "0", "-10", "file-5", "file-1"];
+    let expected = vec!["-10", "-3", "0", "2", "file-1", "file-5"];
+    NaturalSort::natural_sort_with(&mut vec, &options);
+    assert_eq!(vec, expected);
+  }
+
+  #[test]
+  fn test_natural_key_1() {
+    let options = NaturalSortOptions::default();
+
+    let mut vec = vec!["a10", "a9", "a010", "a1"];
+    vec.sort_by_cached_key(|s| NaturalKey::new(s, &options));
+    assert_eq!(vec, vec!["a1", "a9", "a10", "a010"]);
+
+    assert!(NaturalKey::new("x2", &options) < NaturalKey::new("x10", &options));
+    assert!(NaturalKey::new("x10", &options) == NaturalKey::new("x10", &options));
+  }
 }
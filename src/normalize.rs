@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use unicode_normalization::{UnicodeNormalization, is_nfc, is_nfd, is_nfkc, is_nfkd};
+
+pub struct Normalizer {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NormalizationForm {
+  /// Canonical composition (e.g. Linux, Windows)
+  Nfc,
+  /// Canonical decomposition (e.g. names copied from macOS)
+  Nfd,
+  /// Compatibility composition
+  Nfkc,
+  /// Compatibility decomposition
+  Nfkd,
+}
+
+impl NormalizationForm {
+  ///
+  /// Find a normalization form by its modifier name, e.g. "nfc".
+  ///
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "nfc" => Some(NormalizationForm::Nfc),
+      "nfd" => Some(NormalizationForm::Nfd),
+      "nfkc" => Some(NormalizationForm::Nfkc),
+      "nfkd" => Some(NormalizationForm::Nfkd),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Collision {
+  pub normalized: String,
+  pub names: Vec<String>,
+}
+
+impl Normalizer {
+  ///
+  /// Normalize a string to the specified Unicode normalization form.
+  ///
+  /// # Arguments
+  /// * `s` - string to normalize
+  /// * `form` - normalization form
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::normalize::{NormalizationForm, Normalizer};
+  ///
+  /// // "か" + combining voiced sound mark -> "が"
+  /// assert_eq!(Normalizer::normalize("\u{304B}\u{3099}", NormalizationForm::Nfc), "\u{304C}");
+  /// ```
+  ///
+  pub fn normalize(s: &str, form: NormalizationForm) -> String {
+    match form {
+      NormalizationForm::Nfc => s.nfc().collect(),
+      NormalizationForm::Nfd => s.nfd().collect(),
+      NormalizationForm::Nfkc => s.nfkc().collect(),
+      NormalizationForm::Nfkd => s.nfkd().collect(),
+    }
+  }
+
+  ///
+  /// Returns true if the string is already in the specified normalization form.
+  ///
+  pub fn is_normalized(s: &str, form: NormalizationForm) -> bool {
+    match form {
+      NormalizationForm::Nfc => is_nfc(s),
+      NormalizationForm::Nfd => is_nfd(s),
+      NormalizationForm::Nfkc => is_nfkc(s),
+      NormalizationForm::Nfkd => is_nfkd(s),
+    }
+  }
+
+  ///
+  /// Find names which become identical after normalization.
+  ///
+  /// # Arguments
+  /// * `names` - names to check (e.g. file names in the same directory)
+  /// * `form` - normalization form
+  ///
+  /// # Return
+  /// * `Vec<Collision>` - colliding names grouped by normalized name, in order of first appearance
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::normalize::{NormalizationForm, Normalizer};
+  ///
+  /// let names = vec!["\u{304C}.txt", "\u{304B}\u{3099}.txt", "other.txt"];
+  /// let collisions = Normalizer::find_collisions(&names, NormalizationForm::Nfc);
+  /// assert_eq!(collisions.len(), 1);
+  /// assert_eq!(collisions[0].names.len(), 2);
+  /// ```
+  ///
+  pub fn find_collisions(names: &[&str], form: NormalizationForm) -> Vec<Collision> {
+    let mut order: Vec<String> = vec![];
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+
+    for name in names {
+      let normalized = Normalizer::normalize(name, form);
+      let group = groups.entry(normalized.clone()).or_insert_with(|| {
+        order.push(normalized);
+        vec![]
+      });
+
+      if !group.iter().any(|n| n == name) {
+        group.push(name.to_string());
+      }
+    }
+
+    order
+      .into_iter()
+      .filter_map(|normalized| {
+        let names = groups.remove(&normalized).unwrap();
+        if names.len() > 1 {
+          Some(Collision { normalized, names })
+        } else {
+          None
+        }
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Collision, NormalizationForm, Normalizer};
+
+  // "ガ" (U+30AC) and "カ" (U+30AB) + combining voiced sound mark (U+3099)
+  const GA_NFC: &str = "\u{30AC}";
+  const GA_NFD: &str = "\u{30AB}\u{3099}";
+
+  #[test]
+  fn test_normalize_nfc_1() {
+    let s = format!("{}.txt", GA_NFD);
+    let r = Normalizer::normalize(&s, NormalizationForm::Nfc);
+    assert_eq!(format!("{}.txt", GA_NFC), r);
+  }
+
+  #[test]
+  fn test_normalize_nfd_1() {
+    let s = format!("{}.txt", GA_NFC);
+    let r = Normalizer::normalize(&s, NormalizationForm::Nfd);
+    assert_eq!(format!("{}.txt", GA_NFD), r);
+  }
+
+  #[test]
+  fn test_normalize_nfkc_1() {
+    // full-width digits and half-width katakana are folded
+    let s = "１２３\u{FF76}\u{FF9E}";
+    let r = Normalizer::normalize(s, NormalizationForm::Nfkc);
+    assert_eq!(format!("123{}", GA_NFC), r);
+  }
+
+  #[test]
+  fn test_is_normalized_1() {
+    assert!(Normalizer::is_normalized(GA_NFC, NormalizationForm::Nfc));
+    assert!(!Normalizer::is_normalized(GA_NFD, NormalizationForm::Nfc));
+    assert!(Normalizer::is_normalized(GA_NFD, NormalizationForm::Nfd));
+    assert!(Normalizer::is_normalized(
+      "ascii.txt",
+      NormalizationForm::Nfd
+    ));
+  }
+
+  #[test]
+  fn test_find_collisions_1() {
+    let a = format!("{}.txt", GA_NFC);
+    let b = format!("{}.txt", GA_NFD);
+    let names = vec!["readme.md", a.as_str(), "other.txt", b.as_str()];
+
+    let r = Normalizer::find_collisions(&names, NormalizationForm::Nfc);
+
+    assert_eq!(
+      vec![Collision {
+        normalized: a.clone(),
+        names: vec![a.clone(), b.clone()],
+      }],
+      r
+    );
+  }
+
+  #[test]
+  fn test_find_collisions_2() {
+    let names = vec!["a.txt", "b.txt", "a.txt"];
+    let r = Normalizer::find_collisions(&names, NormalizationForm::Nfc);
+    assert!(r.is_empty());
+  }
+}
@@ -1,8 +1,24 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use chrono::Local;
 use regex::Regex;
 
+use crate::case::{Case, CaseStyle};
+use crate::content_hash::{ContentHash, HashAlgorithm};
+use crate::normalize::{NormalizationForm, Normalizer};
+use crate::slug::{Slug, SlugOptions};
+use crate::transliterate::{Transliterate, Transliteration};
+
 pub struct Parser {
   counter: u32,
+  /// first number of `?`
+  start: u32,
+  /// increment of `?`
+  step: u32,
+  /// options of the `slug` modifier
+  slug: SlugOptions,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -10,6 +26,13 @@ pub enum ErrorCode {
   // Ok = 0,
   NoOptionalData,
   InvalidCharacter,
+  UnterminatedBlock,
+  InvalidSource,
+  InvalidModifier,
+  /// the file could not be read, e.g. for `\{sha256}`
+  ReadError,
+  /// the number of `?` is larger than u32::MAX
+  CounterOverflow,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -21,16 +44,90 @@ pub struct Error {
 
 pub struct OptionalData {
   pub file_name: String,
+  /// file to read for hashes of the contents
+  pub path: Option<PathBuf>,
+  /// hashes already computed, a file is read once per algorithm
+  hashes: RefCell<HashMap<HashAlgorithm, String>>,
+}
+
+impl OptionalData {
+  pub fn new(file_name: &str) -> Self {
+    Self {
+      file_name: file_name.to_string(),
+      path: None,
+      hashes: RefCell::new(HashMap::new()),
+    }
+  }
+
+  pub fn with_path(mut self, path: &Path) -> Self {
+    self.path = Some(path.to_path_buf());
+    self
+  }
+
+  /// hash of the contents of `path`
+  fn hash(&self, algorithm: HashAlgorithm) -> Result<String, String> {
+    if let Some(hash) = self.hashes.borrow().get(&algorithm) {
+      return Ok(hash.clone());
+    }
+
+    let path = self.path.as_ref().ok_or("no file to hash")?;
+    let hash =
+      ContentHash::file(path, algorithm).map_err(|e| format!("{}: {}", path.display(), e))?;
+    self.hashes.borrow_mut().insert(algorithm, hash.clone());
+    Ok(hash)
+  }
 }
 
 impl Parser {
   pub fn new() -> Self {
-    Parser { counter: 0 }
+    Parser {
+      counter: 0,
+      start: 1,
+      step: 1,
+      slug: SlugOptions::default(),
+    }
+  }
+
+  ///
+  /// Number files from `start` by `step`, e.g. 10, 20, 30, ...
+  ///
+  pub fn with_counter(mut self, start: u32, step: u32) -> Self {
+    self.start = start;
+    self.step = step;
+    self
+  }
+
+  /// number for `?` of the current parse
+  fn number(&self, column: usize) -> Result<u32, Error> {
+    self
+      .counter
+      .saturating_sub(1)
+      .checked_mul(self.step)
+      .and_then(|n| n.checked_add(self.start))
+      .ok_or_else(|| Error {
+        code: ErrorCode::CounterOverflow,
+        column,
+        message: format!("Counter overflow: the number is larger than {}", u32::MAX),
+      })
+  }
+
+  pub fn with_slug_options(mut self, options: SlugOptions) -> Self {
+    self.slug = options;
+    self
+  }
+
+  ///
+  /// Step the counter back, so the next `parse()` gives the same number again.
+  ///
+  pub fn rewind(&mut self) {
+    self.counter = self.counter.saturating_sub(1);
   }
 
   pub fn parse(&mut self, name: &str, optinal: Option<&OptionalData>) -> Result<String, Error> {
     let mut question_count = 0;
     let mut backslash_flag = false;
+    // contents and column of `\{...}`
+    let mut block: Option<(String, usize)> = None;
 
     self.counter += 1;
 
@@ -38,6 +135,17 @@ impl Parser {
     for (i, c) in name.chars().enumerate() {
       // println!("{}: {}", i, c);
 
+      if let Some((contents, column)) = block.as_mut() {
+        if c == '}' {
+          let s = self.expand_block(contents, *column, optinal)?;
+          ret.extend(s.chars());
+          block = None;
+        } else {
+          contents.push(c);
+        }
+        continue;
+      }
+
       if c == '\\' {
         if !backslash_flag {
           backslash_flag = true;
@@ -51,7 +159,7 @@ impl Parser {
       } else {
         if question_count > 0 {
           // println!("{:01$}", self.counter, question_count);
-          let s = format!("{:01$}", self.counter, question_count);
+          let s = format!("{:01$}", self.number(i - question_count)?, question_count);
           ret.extend(s.chars());
         }
 
@@ -65,6 +173,7 @@ impl Parser {
 
         match c {
           '\\' => ret.push('\\'),
+          '{' => block = Some((String::new(), i)),
           '0' => match optinal {
             Some(opt) => ret.extend(opt.file_name.chars()),
             None => {
@@ -160,8 +269,17 @@ impl Parser {
       }
     }
 
+    if let Some((_, column)) = block {
+      return Err(Error {
+        code: ErrorCode::UnterminatedBlock,
+        column,
+        message: "Unterminated block: missing '}'".to_string(),
+      });
+    }
+
     if question_count > 0 {
-      let s = format!("{:01$}", self.counter, question_count);
+      let column = name.chars().count() - question_count;
+      let s = format!("{:01$}", self.number(column)?, question_count);
       ret.extend(s.chars());
     }
 
@@ -169,6 +287,108 @@ impl Parser {
 
     Ok(ret.iter().collect::<String>())
   }
+
+  ///
+  /// Expand the contents of `\{source|modifier|...}`.
+  ///
+  /// # Sources
+  /// * `0` - file name
+  /// * `sha256`, `blake3`, `crc32` - hash of the contents, the first N characters with e.g. `sha256:8`
+  ///
+  /// # Modifiers
+  /// * `hiragana`, `katakana`, `romaji`, `ascii` - see `Transliteration`
+  /// * `slug` - make safe for shells and URLs, see `Slug`
+  /// * `snake`, `kebab`, `camel`, `pascal`, `title` - see `CaseStyle`
+  /// * `nfc`, `nfd`, `nfkc`, `nfkd` - Unicode normalization, see `NormalizationForm`
+  ///
+  fn expand_block(
+    &self,
+    contents: &str,
+    column: usize,
+    optinal: Option<&OptionalData>,
+  ) -> Result<String, Error> {
+    let mut parts = contents.split('|');
+    let source = parts.next().unwrap_or_default();
+
+    let mut value = match source {
+      "0" => match optinal {
+        Some(opt) => opt.file_name.clone(),
+        None => {
+          return Err(Error {
+            code: ErrorCode::NoOptionalData,
+            column,
+            message: "OptionalData not specified".to_string(),
+          });
+        }
+      },
+      _ => match Parser::hash_source(source) {
+        Some((algorithm, length)) => {
+          let opt = optinal.ok_or(Error {
+            code: ErrorCode::NoOptionalData,
+            column,
+            message: "OptionalData not specified".to_string(),
+          })?;
+          let hash = opt.hash(algorithm).map_err(|message| Error {
+            code: ErrorCode::ReadError,
+            column,
+            message,
+          })?;
+          match length {
+            Some(length) => hash.chars().take(length).collect(),
+            None => hash,
+          }
+        }
+        None => {
+          return Err(Error {
+            code: ErrorCode::InvalidSource,
+            column,
+            message: format!("Invalid source: {}", source),
+          });
+        }
+      },
+    };
+
+    for modifier in parts {
+      if modifier == "slug" {
+        value = Slug::apply(&value, &self.slug);
+        continue;
+      }
+      if let Some(style) = CaseStyle::from_name(modifier) {
+        value = Case::convert(&value, style);
+        continue;
+      }
+      if let Some(form) = NormalizationForm::from_name(modifier) {
+        value = Normalizer::normalize(&value, form);
+        continue;
+      }
+
+      value = match Transliteration::from_name(modifier) {
+        Some(t) => Transliterate::apply(&value, t),
+        None => {
+          return Err(Error {
+            code: ErrorCode::InvalidModifier,
+            column,
+            message: format!("Invalid modifier: {}", modifier),
+          });
+        }
+      };
+    }
+
+    Ok(value)
+  }
+
+  /// algorithm and length of e.g. "sha256:8"
+  fn hash_source(source: &str) -> Option<(HashAlgorithm, Option<usize>)> {
+    let (name, length) = match source.split_once(':') {
+      Some((name, length)) => match length.parse::<usize>() {
+        Ok(length) if length > 0 => (name, Some(length)),
+        _ => return None,
+      },
+      None => (source, None),
+    };
+
+    HashAlgorithm::from_name(name).map(|algorithm| (algorithm, length))
+  }
 }
 
 #[cfg(test)]
@@ -176,6 +396,7 @@ mod tests {
   use chrono::Local;
 
   use crate::parser::{Error, ErrorCode, OptionalData, Parser};
+  use crate::slug::SlugOptions;
 
   #[test]
   fn test_parse() {
@@ -190,9 +411,7 @@ mod tests {
   #[test]
   fn test_parse_with_zero_1() {
     let mut p = Parser::new();
-    let data = OptionalData {
-      file_name: String::from("test.txt"),
-    };
+    let data = OptionalData::new("test.txt");
 
     let name = "test\\0";
     let r = p.parse(name, Some(&data));
@@ -277,6 +496,34 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_parse_with_counter_1() {
+    let mut p = Parser::new().with_counter(10, 5);
+
+    assert_eq!(String::from("010.txt"), p.parse("???.txt", None).unwrap());
+    assert_eq!(String::from("015.txt"), p.parse("???.txt", None).unwrap());
+    p.rewind();
+    assert_eq!(String::from("015.txt"), p.parse("???.txt", None).unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_counter_overflow_1() {
+    let mut p = Parser::new().with_counter(u32::MAX - 1, 1);
+
+    assert_eq!(String::from("4294967294"), p.parse("?", None).unwrap());
+    assert_eq!(String::from("4294967295"), p.parse("?", None).unwrap());
+    let e = p.parse("a_??.txt", None).unwrap_err();
+    assert_eq!(ErrorCode::CounterOverflow, e.code);
+    assert_eq!(2, e.column);
+
+    let mut p = Parser::new().with_counter(1, u32::MAX);
+    assert!(p.parse("?", None).is_ok());
+    assert_eq!(
+      ErrorCode::CounterOverflow,
+      p.parse("x?", None).unwrap_err().code
+    );
+  }
+
   #[test]
   fn test_parse_with_datetime_4year_1() {
     let mut p = Parser::new();
@@ -547,4 +794,155 @@ mod tests {
       r.unwrap_err()
     )
   }
+
+  #[test]
+  fn test_parse_with_block_1() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("ねこ.txt");
+
+    let name = "\\{0}";
+    let r = p.parse(name, Some(&data));
+
+    assert_eq!(String::from("ねこ.txt"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_transliteration_1() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("ねこ.txt");
+
+    let name = "\\{0|katakana}_\\{0|romaji}";
+    let r = p.parse(name, Some(&data));
+
+    assert_eq!(String::from("ネコ.txt_neko.txt"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_transliteration_2() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("Crème_ねこ.txt");
+
+    let name = "???_\\{0|romaji|ascii}";
+    let r = p.parse(name, Some(&data));
+
+    assert_eq!(String::from("001_Creme_neko.txt"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_slug_1() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("My Photo (1).jpg");
+
+    let r = p.parse("\\{0|slug}", Some(&data));
+    assert_eq!(String::from("My-Photo-1.jpg"), r.unwrap());
+
+    let mut p = Parser::new().with_slug_options(SlugOptions {
+      replacement: Some('_'),
+      allow: "()".to_string(),
+    });
+    let r = p.parse("???_\\{0|slug}", Some(&data));
+    assert_eq!(String::from("001_My_Photo_(1).jpg"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_normalize_1() {
+    let mut p = Parser::new();
+    // "ガ" decomposed, as in names copied from macOS
+    let data = OptionalData::new("\u{30AB}\u{3099}.txt");
+
+    let r = p.parse("\\{0|nfc}", Some(&data));
+    assert_eq!(String::from("\u{30AC}.txt"), r.unwrap());
+    let r = p.parse("\\{0|nfc|nfd}", Some(&data));
+    assert_eq!(String::from("\u{30AB}\u{3099}.txt"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_case_1() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("userProfile Card.TSX");
+
+    let r = p.parse("\\{0|kebab}", Some(&data));
+    assert_eq!(String::from("user-profile-card.TSX"), r.unwrap());
+
+    let r = p.parse("??_\\{0|ascii|pascal}", Some(&data));
+    assert_eq!(String::from("02_UserProfileCard.TSX"), r.unwrap());
+  }
+
+  #[test]
+  fn test_parse_with_block_hash_1() {
+    let path = std::path::Path::new("test_parse_hash_1.txt");
+    std::fs::write(path, "abc").unwrap();
+    let mut p = Parser::new();
+    let data = OptionalData::new("a.txt").with_path(path);
+
+    let r1 = p.parse("\\{crc32}.txt", Some(&data));
+    std::fs::remove_file(path).unwrap();
+    // computed once, not read again
+    let r2 = p.parse("\\{sha256:8}_\\{crc32:4}.txt", Some(&data));
+    let r3 = p.parse("\\{sha256:0}", Some(&data));
+    let r4 = p.parse("\\{blake3}", Some(&data));
+
+    assert_eq!(String::from("352441c2.txt"), r1.unwrap());
+    assert_eq!(ErrorCode::ReadError, r2.unwrap_err().code);
+    assert_eq!(ErrorCode::InvalidSource, r3.unwrap_err().code);
+    assert_eq!(ErrorCode::ReadError, r4.unwrap_err().code);
+
+    let data = OptionalData::new("a.txt");
+    data.hashes.borrow_mut().insert(
+      crate::content_hash::HashAlgorithm::Sha256,
+      "ba7816bf8f01cfea".to_string(),
+    );
+    assert_eq!(
+      String::from("ba7816bf_a.txt"),
+      p.parse("\\{sha256:8}_\\0", Some(&data)).unwrap()
+    );
+  }
+
+  #[test]
+  fn test_parse_block_invalid_1() {
+    let mut p = Parser::new();
+    let data = OptionalData::new("test.txt");
+
+    let name = "test\\{0|unknown}";
+    let r = p.parse(name, Some(&data));
+
+    assert_eq!(
+      Error {
+        column: 5,
+        code: ErrorCode::InvalidModifier,
+        message: "Invalid modifier: unknown".to_string()
+      },
+      r.unwrap_err()
+    )
+  }
+
+  #[test]
+  fn test_parse_block_invalid_2() {
+    let mut p = Parser::new();
+
+    let name = "test\\{0|romaji";
+    let r = p.parse(name, None);
+
+    assert_eq!(ErrorCode::UnterminatedBlock, r.unwrap_err().code)
+  }
+
+  #[test]
+  fn test_parse_block_invalid_3() {
+    let mut p = Parser::new();
+
+    let name = "\\{x}";
+    let r = p.parse(name, None);
+
+    assert_eq!(ErrorCode::InvalidSource, r.unwrap_err().code)
+  }
+
+  #[test]
+  fn test_parse_block_invalid_4() {
+    let mut p = Parser::new();
+
+    let name = "\\{0|romaji}";
+    let r = p.parse(name, None);
+
+    assert_eq!(ErrorCode::NoOptionalData, r.unwrap_err().code)
+  }
 }
@@ -1,6 +1,6 @@
 use std::{
   fs::{self},
-  path::Path,
+  path::{Component, Path},
 };
 
 use serde::{
@@ -10,6 +10,8 @@ use serde::{
 };
 
 use crate::filesystem::FileSystem;
+use crate::tasks::context::RenameEntry;
+use crate::validity::{Platform, Validity, Violation};
 
 pub struct Plan {
   root: PlanRoot,
@@ -28,6 +30,9 @@ pub struct PlanElementV1 {
   src: String,
   dest: String,
   id: String,
+  /// moved to another file system by copying, the id changes
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  cross_device: bool,
 }
 
 impl PlanRootV1 {
@@ -63,6 +68,7 @@ impl PlanElementV1 {
       src: src.to_string(),
       dest: dest.to_string(),
       id: id.to_string(),
+      cross_device: false,
     }
   }
 
@@ -77,6 +83,10 @@ impl PlanElementV1 {
   pub fn get_id(&self) -> &str {
     &self.id
   }
+
+  pub fn is_cross_device(&self) -> bool {
+    self.cross_device
+  }
 }
 
 pub type PlanRoot = PlanRootV1;
@@ -108,27 +118,100 @@ impl Plan {
     return "linux";
   }
 
+  ///
+  /// Plan of the entries with new names, e.g. of a dry run.
+  ///
+  /// # Arguments
+  /// * `root` - directory the entries were enumerated in
+  /// * `entries` - paths starting with `root`
+  ///
+  pub fn from_entries(root: &Path, entries: &[RenameEntry]) -> Result<Plan, String> {
+    let mut plan = Plan::new(root);
+    for entry in entries {
+      if let Some(dest) = entry.dest.as_ref() {
+        let (src, dest) = match (Plan::relative(root, &entry.src), Plan::relative(root, dest)) {
+          (Some(src), Some(dest)) => (src, dest),
+          _ => {
+            return Err(format!(
+              "{} -> {}: outside {}",
+              entry.src.display(),
+              dest.display(),
+              root.display()
+            ));
+          }
+        };
+        plan.add(Path::new(&src), &dest)?;
+      }
+    }
+    Ok(plan)
+  }
+
+  ///
+  /// Add a rename.
+  ///
+  /// # Arguments
+  /// * `src` - path relative to the root, or absolute in the root
+  /// * `dest` - new path relative to the root, e.g. "2024/a.jpg"
+  ///
   pub fn add(&mut self, src: &Path, dest: &String) -> Result<(), String> {
-    if !src.exists() {
+    let root = Path::new(&self.root.path).to_path_buf();
+    let p = root.join(src);
+    if !p.exists() {
       return Err(format!("{} not found.", src.display()));
     }
+    let relative = match Plan::relative(&root, &p) {
+      Some(relative) => relative,
+      None => return Err(format!("{}: outside {}", src.display(), root.display())),
+    };
+
+    // names must be valid on the platform the plan is for, dest may move to a subdirectory
+    if let Some(platform) = Platform::from_name(&self.root.platform) {
+      let violations: Vec<Violation> = dest
+        .split('/')
+        .filter(|n| *n != "." && *n != "..")
+        .flat_map(|n| Validity::check(n, platform))
+        .collect();
+      if !violations.is_empty() {
+        let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        return Err(format!("{}: {}", dest, violations.join(", ")));
+      }
+    }
 
-    let p = Path::new(&self.root.path).join(src);
     let id = FileSystem::get_id_by_filename(&p);
 
     if id.is_err() {
       return Err(id.unwrap_err());
     }
 
+    let cross_device = match FileSystem::is_same_device(&p, &root.join(dest)) {
+      Ok(same) => !same,
+      Err(e) => return Err(e.to_string()),
+    };
+
     self.root.plan.push(PlanElement {
-      src: src.file_name().unwrap().to_string_lossy().to_string(),
+      src: relative,
       dest: dest.to_string(),
       id: id.unwrap(),
+      cross_device,
     });
 
     Ok(())
   }
 
+  /// `path` relative to `root` with '/' separators, e.g. "sub/a.jpg", none if outside
+  fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rest = path.strip_prefix(root).ok()?;
+    let mut names = vec![];
+    for c in rest.components() {
+      match c {
+        Component::Normal(name) => names.push(name.to_string_lossy().to_string()),
+        Component::CurDir => {}
+        _ => return None,
+      }
+    }
+    Some(names.join("/"))
+  }
+
   pub fn get_root(&self) -> &PlanRoot {
     &self.root
   }
@@ -193,9 +276,10 @@ impl Plan {
 
 #[cfg(test)]
 mod tests {
-  use std::path::Path;
+  use std::{fs, path::Path};
 
   use super::Plan;
+  use crate::tasks::context::RenameEntry;
 
   #[test]
   fn test() {
@@ -218,5 +302,34 @@ mod tests {
     assert_eq!(p2.get_root().plan.get(0).unwrap().src, "README.md");
     assert_eq!(p2.get_root().plan.get(0).unwrap().dest, "RENAMED.md");
     assert!(p2.get_root().plan.get(0).unwrap().id.len() > 0);
+    assert!(!p2.get_root().plan.get(0).unwrap().is_cross_device());
+  }
+
+  #[test]
+  fn test_nested() {
+    let dir = Path::new("test_plan_nested");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("a.jpg"), "a").unwrap();
+
+    let mut entry = RenameEntry::new(&dir.join("sub").join("a.jpg"));
+    entry.dest = Some(dir.join("sub").join("x.jpg"));
+    let plan = Plan::from_entries(dir, &[entry]);
+    let mut p = Plan::new(dir);
+    let missing = p.add(Path::new("a.jpg"), &"x.jpg".to_string());
+    fs::remove_dir_all(dir).unwrap();
+
+    let plan = plan.unwrap();
+    assert_eq!("sub/a.jpg", plan.get_plans()[0].get_source());
+    assert_eq!("sub/x.jpg", plan.get_plans()[0].get_destination());
+    // "a.jpg" is looked up in the root, not in "sub"
+    assert!(missing.is_err());
+  }
+
+  #[test]
+  fn test_invalid_name() {
+    let mut p = Plan::new(Path::new("."));
+
+    let add_result = p.add(Path::new("README.md"), &"a//b.md".to_string());
+    assert!(add_result.unwrap_err().contains("empty name"));
   }
 }
@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+
+use unicode_width::UnicodeWidthStr;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+  Equal,
+  /// only in the old name
+  Delete,
+  /// only in the new name
+  Insert,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffSegment {
+  pub kind: DiffKind,
+  pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewEntry {
+  pub src: PathBuf,
+  pub dest: PathBuf,
+  /// reason the new name is rejected, e.g. "duplicated"
+  pub conflict: Option<String>,
+}
+
+///
+/// Side-by-side preview of a dry run.
+/// Old and new names are aligned and grouped by directory, and the changed characters are highlighted.
+///
+/// # Examples
+/// ```
+/// use nae::preview::Preview;
+/// use std::path::Path;
+///
+/// let mut preview = Preview::new(false);
+/// preview.add(Path::new("dir/IMG_001.jpg"), Path::new("dir/photo_001.jpg"), None);
+/// preview.add(Path::new("dir/IMG_002.jpg"), Path::new("dir/photo_001.jpg"), Some("duplicated"));
+/// print!("{}", preview.render());
+/// ```
+///
+pub struct Preview {
+  entries: Vec<PreviewEntry>,
+  /// highlight with ANSI escape sequences
+  color: bool,
+}
+
+impl Preview {
+  pub fn new(color: bool) -> Self {
+    Self {
+      entries: vec![],
+      color,
+    }
+  }
+
+  pub fn add(&mut self, src: &Path, dest: &Path, conflict: Option<&str>) {
+    self.entries.push(PreviewEntry {
+      src: src.to_path_buf(),
+      dest: dest.to_path_buf(),
+      conflict: conflict.map(|c| c.to_string()),
+    });
+  }
+
+  pub fn get_entries(&self) -> &Vec<PreviewEntry> {
+    &self.entries
+  }
+
+  ///
+  /// Render the preview, one header line per directory followed by its entries.
+  ///
+  /// # Return
+  /// * `String` - lines like `"  old.txt  -> new.txt"`, conflicts are marked with `!`
+  ///
+  pub fn render(&self) -> String {
+    let mut ret = String::new();
+
+    // directories in order of first appearance
+    let mut dirs: Vec<&Path> = vec![];
+    for entry in &self.entries {
+      let dir = entry.src.parent().unwrap_or(Path::new(""));
+      if !dirs.contains(&dir) {
+        dirs.push(dir);
+      }
+    }
+
+    for dir in dirs {
+      let entries: Vec<&PreviewEntry> = self
+        .entries
+        .iter()
+        .filter(|e| e.src.parent().unwrap_or(Path::new("")) == dir)
+        .collect();
+      let names: Vec<(String, String)> = entries
+        .iter()
+        .map(|e| (Preview::file_name(&e.src), Preview::dest_name(dir, &e.dest)))
+        .collect();
+      let width = names.iter().map(|(old, _)| old.width()).max().unwrap_or(0);
+
+      ret.push_str(&format!("{}/\n", dir.display()));
+
+      for (entry, (old, new)) in entries.iter().zip(names.iter()) {
+        let padding = " ".repeat(width - old.width());
+
+        if let Some(conflict) = entry.conflict.as_ref() {
+          let line = format!("! {}{} -> {}  ({})", old, padding, new, conflict);
+          ret.push_str(&self.paint(RED, &line));
+        } else if old == new {
+          let line = format!("  {}{} (unchanged)", old, padding);
+          ret.push_str(&self.paint(DIM, &line));
+        } else {
+          let diff = Preview::diff_chars(old, new);
+          ret.push_str(&format!(
+            "  {}{} -> {}",
+            self.highlight(&diff, DiffKind::Delete),
+            padding,
+            self.highlight(&diff, DiffKind::Insert)
+          ));
+        }
+        ret.push('\n');
+      }
+    }
+
+    ret
+  }
+
+  ///
+  /// Character-level diff of two names, based on the longest common subsequence.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::preview::{DiffKind, Preview};
+  ///
+  /// let diff = Preview::diff_chars("IMG_1.jpg", "photo_1.jpg");
+  /// assert_eq!(diff[0].kind, DiffKind::Delete);
+  /// assert_eq!(diff[0].text, "IMG");
+  /// assert_eq!(diff[1].kind, DiffKind::Insert);
+  /// assert_eq!(diff[1].text, "photo");
+  /// assert_eq!(diff[2].kind, DiffKind::Equal);
+  /// assert_eq!(diff[2].text, "_1.jpg");
+  /// ```
+  ///
+  pub fn diff_chars(old: &str, new: &str) -> Vec<DiffSegment> {
+    let a: Vec<char> = old.chars().collect();
+    let b: Vec<char> = new.chars().collect();
+
+    // lcs[i][j]: length of the LCS of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+      for j in (0..b.len()).rev() {
+        lcs[i][j] = if a[i] == b[j] {
+          lcs[i + 1][j + 1] + 1
+        } else {
+          lcs[i + 1][j].max(lcs[i][j + 1])
+        };
+      }
+    }
+
+    let mut ret: Vec<DiffSegment> = vec![];
+    let mut push = |kind: DiffKind, c: char| match ret.last_mut() {
+      Some(last) if last.kind == kind => last.text.push(c),
+      _ => ret.push(DiffSegment {
+        kind,
+        text: c.to_string(),
+      }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+      if i < a.len() && j < b.len() && a[i] == b[j] {
+        push(DiffKind::Equal, a[i]);
+        i += 1;
+        j += 1;
+      } else if j >= b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+        push(DiffKind::Delete, a[i]);
+        i += 1;
+      } else {
+        push(DiffKind::Insert, b[j]);
+        j += 1;
+      }
+    }
+
+    ret
+  }
+
+  fn file_name(path: &Path) -> String {
+    path
+      .file_name()
+      .map(|n| n.to_string_lossy().to_string())
+      .unwrap_or_default()
+  }
+
+  /// path relative to `dir` if `dest` is inside it (e.g. "2024/01/a.jpg"), the whole path otherwise
+  fn dest_name(dir: &Path, dest: &Path) -> String {
+    match dest.strip_prefix(dir) {
+      Ok(rest) if !dir.as_os_str().is_empty() => rest.display().to_string(),
+      _ => dest.display().to_string(),
+    }
+  }
+
+  /// one side of the diff, with the segments of `kind` highlighted
+  fn highlight(&self, diff: &[DiffSegment], kind: DiffKind) -> String {
+    let color = if kind == DiffKind::Delete { RED } else { GREEN };
+
+    diff
+      .iter()
+      .filter(|s| s.kind == DiffKind::Equal || s.kind == kind)
+      .map(|s| {
+        if s.kind == kind {
+          self.paint(color, &s.text)
+        } else {
+          s.text.clone()
+        }
+      })
+      .collect()
+  }
+
+  fn paint(&self, color: &str, s: &str) -> String {
+    if self.color {
+      format!("{}{}{}", color, s, RESET)
+    } else {
+      s.to_string()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{DiffKind, Preview};
+  use std::path::Path;
+
+  #[test]
+  fn test_diff_chars_1() {
+    let diff = Preview::diff_chars("a.txt", "a.txt");
+    assert_eq!(1, diff.len());
+    assert_eq!(DiffKind::Equal, diff[0].kind);
+
+    let diff = Preview::diff_chars("", "b");
+    assert_eq!(1, diff.len());
+    assert_eq!(DiffKind::Insert, diff[0].kind);
+  }
+
+  #[test]
+  fn test_diff_chars_2() {
+    let diff = Preview::diff_chars("第1話.mp4", "第01話.mp4");
+    let kinds: Vec<(DiffKind, &str)> = diff.iter().map(|s| (s.kind, s.text.as_str())).collect();
+    assert_eq!(
+      vec![
+        (DiffKind::Equal, "第"),
+        (DiffKind::Insert, "0"),
+        (DiffKind::Equal, "1話.mp4"),
+      ],
+      kinds
+    );
+  }
+
+  #[test]
+  fn test_render_1() {
+    let mut preview = Preview::new(false);
+    preview.add(Path::new("d/a.txt"), Path::new("d/x_a.txt"), None);
+    preview.add(Path::new("d/bbb.txt"), Path::new("d/bbb.txt"), None);
+    preview.add(
+      Path::new("e/c.txt"),
+      Path::new("e/x.txt"),
+      Some("duplicated"),
+    );
+
+    assert_eq!(
+      "d/\n  a.txt   -> x_a.txt\n  bbb.txt (unchanged)\ne/\n! c.txt -> x.txt  (duplicated)\n",
+      preview.render()
+    );
+  }
+
+  #[test]
+  fn test_render_color_1() {
+    let mut preview = Preview::new(true);
+    preview.add(Path::new("d/a.txt"), Path::new("d/b.txt"), None);
+
+    assert_eq!(
+      "d/\n  \x1b[31ma\x1b[0m.txt -> \x1b[32mb\x1b[0m.txt\n",
+      preview.render()
+    );
+  }
+
+  #[test]
+  fn test_render_wide_1() {
+    // full-width characters take 2 columns
+    let mut preview = Preview::new(false);
+    preview.add(Path::new("d/ねこ.txt"), Path::new("d/neko.txt"), None);
+    preview.add(Path::new("d/inu_x.txt"), Path::new("d/inu.txt"), None);
+
+    let r = preview.render();
+    let lines: Vec<&str> = r.lines().collect();
+    assert_eq!("  ねこ.txt  -> neko.txt", lines[1]);
+    assert_eq!("  inu_x.txt -> inu.txt", lines[2]);
+  }
+}
@@ -0,0 +1,198 @@
+use std::io::Write;
+
+use crate::event::RenameEvent;
+
+const BAR_WIDTH: usize = 30;
+
+///
+/// Render `RenameEvent`s for the command line.
+/// Draws a progress bar on a terminal, and writes one line per event otherwise (e.g. redirected to a log).
+///
+/// # Examples
+/// ```
+/// use nae::event::RenameEvent;
+/// use nae::progress::ProgressReporter;
+/// use std::{path::PathBuf, time::Duration};
+///
+/// let mut reporter = ProgressReporter::new(vec![], false);
+/// reporter.report(&RenameEvent::Renamed {
+///   src: PathBuf::from("a.txt"),
+///   dest: PathBuf::from("b.txt"),
+///   elapsed: Duration::from_millis(1),
+/// });
+/// ```
+///
+pub struct ProgressReporter<W: Write> {
+  out: W,
+  tty: bool,
+  /// also write scanned and planned files as lines
+  pub verbose: bool,
+  pub scanned: usize,
+  pub planned: usize,
+  pub renamed: usize,
+  pub skipped: usize,
+  pub conflicted: usize,
+  pub failed: usize,
+  /// a progress bar is drawn on the current line
+  drawing: bool,
+}
+
+impl<W: Write> ProgressReporter<W> {
+  pub fn new(out: W, tty: bool) -> Self {
+    Self {
+      out,
+      tty,
+      verbose: false,
+      scanned: 0,
+      planned: 0,
+      renamed: 0,
+      skipped: 0,
+      conflicted: 0,
+      failed: 0,
+      drawing: false,
+    }
+  }
+
+  pub fn get_output(&self) -> &W {
+    &self.out
+  }
+
+  pub fn report(&mut self, event: &RenameEvent) {
+    match event {
+      RenameEvent::Scanned { .. } => self.scanned += 1,
+      RenameEvent::Planned { .. } => self.planned += 1,
+      RenameEvent::Renamed { .. } => self.renamed += 1,
+      RenameEvent::Skipped { .. } => self.skipped += 1,
+      RenameEvent::Conflict { .. } => self.conflicted += 1,
+      RenameEvent::Failed { .. } => self.failed += 1,
+    }
+
+    let line = match event {
+      RenameEvent::Scanned { path } if self.verbose => Some(format!("scanned: {}", path.display())),
+      RenameEvent::Planned { src, dest } if self.verbose => {
+        Some(format!("planned: {} -> {}", src.display(), dest.display()))
+      }
+      RenameEvent::Renamed { src, dest, .. } if !self.tty || self.verbose => {
+        Some(format!("renamed: {} -> {}", src.display(), dest.display()))
+      }
+      RenameEvent::Skipped { path, reason } if !self.tty || self.verbose => {
+        Some(format!("skipped: {} ({})", path.display(), reason))
+      }
+      RenameEvent::Conflict { path, message, .. } => {
+        Some(format!("conflict: {}: {}", path.display(), message))
+      }
+      RenameEvent::Failed { path, message, .. } => {
+        Some(format!("failed: {}: {}", path.display(), message))
+      }
+      _ => None,
+    };
+
+    // errors on the console are ignored, as for `eprintln!()`
+    if let Some(line) = line {
+      self.clear();
+      let _ = writeln!(self.out, "{}", line);
+    }
+
+    if self.tty {
+      self.draw(event);
+    }
+  }
+
+  ///
+  /// End the progress bar line.
+  ///
+  pub fn finish(&mut self) {
+    if self.drawing {
+      let _ = writeln!(self.out);
+      self.drawing = false;
+    }
+    let _ = self.out.flush();
+  }
+
+  fn clear(&mut self) {
+    if self.drawing {
+      let _ = write!(self.out, "\r\x1b[K");
+      self.drawing = false;
+    }
+  }
+
+  fn draw(&mut self, event: &RenameEvent) {
+    let status = match event {
+      RenameEvent::Scanned { .. } => format!("scanning: {} files", self.scanned),
+      RenameEvent::Planned { .. } | RenameEvent::Conflict { .. } => {
+        format!("planning: {}/{}", self.planned, self.scanned)
+      }
+      _ => {
+        let done = self.renamed + self.skipped + self.failed;
+        let total = self.planned.max(done).max(1);
+        let filled = BAR_WIDTH * done / total;
+        format!(
+          "[{}{}] {}/{}",
+          "#".repeat(filled),
+          "-".repeat(BAR_WIDTH - filled),
+          done,
+          self.planned.max(done)
+        )
+      }
+    };
+
+    let _ = write!(self.out, "\r\x1b[K{}", status);
+    let _ = self.out.flush();
+    self.drawing = true;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ProgressReporter;
+  use crate::event::RenameEvent;
+  use std::{path::PathBuf, time::Duration};
+
+  fn events() -> Vec<RenameEvent> {
+    vec![
+      RenameEvent::Scanned {
+        path: PathBuf::from("a.txt"),
+      },
+      RenameEvent::Planned {
+        src: PathBuf::from("a.txt"),
+        dest: PathBuf::from("b.txt"),
+      },
+      RenameEvent::Renamed {
+        src: PathBuf::from("a.txt"),
+        dest: PathBuf::from("b.txt"),
+        elapsed: Duration::from_millis(1),
+      },
+      RenameEvent::Skipped {
+        path: PathBuf::from("c.txt"),
+        reason: "same name".to_string(),
+      },
+    ]
+  }
+
+  #[test]
+  fn test_plain_1() {
+    let mut reporter = ProgressReporter::new(vec![], false);
+    for e in events() {
+      reporter.report(&e);
+    }
+    reporter.finish();
+
+    let out = String::from_utf8(reporter.get_output().clone()).unwrap();
+    assert_eq!("renamed: a.txt -> b.txt\nskipped: c.txt (same name)\n", out);
+    assert_eq!(1, reporter.renamed);
+    assert_eq!(1, reporter.skipped);
+  }
+
+  #[test]
+  fn test_tty_1() {
+    let mut reporter = ProgressReporter::new(vec![], true);
+    for e in events() {
+      reporter.report(&e);
+    }
+    reporter.finish();
+
+    let out = String::from_utf8(reporter.get_output().clone()).unwrap();
+    assert!(!out.contains("renamed:"));
+    assert!(out.ends_with(&format!("[{}] 2/2\n", "#".repeat(30))));
+  }
+}
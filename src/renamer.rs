@@ -1,5 +1,9 @@
 use crate::filesystem::FileSystem;
+use crate::normalize::{NormalizationForm, Normalizer};
 use crate::parser::{OptionalData, Parser};
+use crate::rules::RuleSet;
+use crate::task::Invoker;
+use crate::tasks::rename::{RenameJournal, RenameTask};
 use std::io::{Error, ErrorKind};
 use std::path::Path;
 
@@ -42,18 +46,11 @@ impl Renamer {
     name_pattern: &str,
     dry_run: bool,
   ) -> std::io::Result<String> {
-    let optional_data = OptionalData {
-      file_name: target.file_name().unwrap().to_str().unwrap().to_string(),
-    };
+    let optional_data =
+      OptionalData::new(target.file_name().unwrap().to_str().unwrap()).with_path(target);
 
     match self.parser.parse(name_pattern, Some(&optional_data)) {
-      Ok(name) => {
-        let new_name = target.with_file_name(name);
-        if !dry_run {
-          FileSystem::rename(target, &new_name.to_str().unwrap())?;
-        }
-        Ok(new_name.to_str().unwrap().to_string())
-      }
+      Ok(name) => Renamer::apply(target, &name, dry_run),
       // parsing error
       Err(e) => {
         println!("{}", e.message);
@@ -61,10 +58,202 @@ impl Renamer {
       }
     }
   }
+
+  ///
+  /// Rename files to the new names as a transaction.
+  /// If one of the renames fails, the files already renamed are restored.
+  ///
+  /// # Arguments
+  /// * `targets` - paths to files
+  /// * `name_pattern` - new name
+  /// * `dry_run` - dry run
+  ///
+  /// # Return
+  /// * `std::io::Result<Vec<String>>` - new names
+  ///
+  /// # Example
+  /// ```
+  /// use nae::renamer::Renamer;
+  /// use std::path::Path;
+  ///
+  /// let mut renamer = Renamer::new();
+  /// let targets = vec![Path::new("a.txt"), Path::new("b.txt")];
+  /// let new_names = renamer.rename_all(&targets, "file???.txt", true).unwrap();
+  /// assert_eq!(new_names, vec!["file001.txt", "file002.txt"]);
+  /// ```
+  ///
+  pub fn rename_all(
+    &mut self,
+    targets: &[&Path],
+    name_pattern: &str,
+    dry_run: bool,
+  ) -> std::io::Result<Vec<String>> {
+    let mut new_names = vec![];
+    for target in targets {
+      new_names.push(self.rename(target, name_pattern, true)?);
+    }
+
+    if dry_run {
+      return Ok(new_names);
+    }
+
+    let mut journal = RenameJournal::default();
+    let mut invoker = Invoker::new(&mut journal);
+    for (target, new_name) in targets.iter().zip(new_names.iter()) {
+      invoker.add(RenameTask::new(target, Path::new(new_name)));
+    }
+
+    match invoker.execute_all() {
+      Ok(()) => Ok(new_names),
+      Err(e) => Err(Error::other(e.message)),
+    }
+  }
+
+  ///
+  /// Rename file to the specified Unicode normalization form.
+  ///
+  /// # Arguments
+  /// * `target` - path to file
+  /// * `form` - normalization form
+  /// * `dry_run` - dry run
+  ///
+  /// # Return
+  /// * `std::io::Result<String>` - new name
+  ///
+  /// # Example
+  /// ```
+  /// use nae::normalize::NormalizationForm;
+  /// use nae::renamer::Renamer;
+  /// use std::path::Path;
+  ///
+  /// let mut renamer = Renamer::new();
+  /// let path = Path::new("\u{30AB}\u{3099}.txt");
+  /// let new_name = renamer.normalize(&path, NormalizationForm::Nfc, true).unwrap();
+  /// assert_eq!(new_name, "\u{30AC}.txt");
+  /// ```
+  ///
+  pub fn normalize(
+    &mut self,
+    target: &Path,
+    form: NormalizationForm,
+    dry_run: bool,
+  ) -> std::io::Result<String> {
+    self.transform(target, |s| Normalizer::normalize(s, form), dry_run)
+  }
+
+  ///
+  /// Rename file by applying the substitutions of the rules.
+  ///
+  /// # Arguments
+  /// * `target` - path to file
+  /// * `rules` - substitutions, e.g. loaded from a rules file
+  /// * `dry_run` - dry run
+  ///
+  /// # Return
+  /// * `std::io::Result<String>` - new name
+  ///
+  /// # Example
+  /// ```
+  /// use nae::renamer::Renamer;
+  /// use nae::rules::RuleSet;
+  /// use std::path::Path;
+  ///
+  /// let mut renamer = Renamer::new();
+  /// let rules = RuleSet::from_json(r#"{"rules": [{"find": " ", "replace": "_"}]}"#).unwrap();
+  /// let new_name = renamer.replace(Path::new("a b.txt"), &rules, true).unwrap();
+  /// assert_eq!(new_name, "a_b.txt");
+  /// ```
+  ///
+  pub fn replace(
+    &mut self,
+    target: &Path,
+    rules: &RuleSet,
+    dry_run: bool,
+  ) -> std::io::Result<String> {
+    self.transform(target, |s| rules.apply(s), dry_run)
+  }
+
+  ///
+  /// Rename file to the name returned by the transform.
+  /// Never overwrites another existing file (e.g. the NFC twin of a NFD name).
+  ///
+  /// # Arguments
+  /// * `target` - path to file
+  /// * `transform` - function to convert the current file name to the new one
+  /// * `dry_run` - dry run
+  ///
+  /// # Return
+  /// * `std::io::Result<String>` - new name
+  ///
+  /// # Example
+  /// ```
+  /// use nae::renamer::Renamer;
+  /// use nae::transliterate::Transliterate;
+  /// use std::path::Path;
+  ///
+  /// let mut renamer = Renamer::new();
+  /// let path = Path::new("ねこ.txt");
+  /// let new_name = renamer.transform(&path, Transliterate::kana_to_romaji, true).unwrap();
+  /// assert_eq!(new_name, "neko.txt");
+  /// ```
+  ///
+  pub fn transform<F>(
+    &mut self,
+    target: &Path,
+    transform: F,
+    dry_run: bool,
+  ) -> std::io::Result<String>
+  where
+    F: Fn(&str) -> String,
+  {
+    let file_name = target.file_name().unwrap().to_str().unwrap();
+    let name = transform(file_name);
+
+    if name == file_name {
+      // nothing to do
+      return Ok(target.to_str().unwrap().to_string());
+    }
+
+    // a case-only change is the same file on case-insensitive file systems
+    let dest = target.with_file_name(&name);
+    if dest.exists() && !FileSystem::is_same_file(target, &dest) {
+      return Err(Error::new(
+        ErrorKind::AlreadyExists,
+        format!("{} already exists", name),
+      ));
+    }
+
+    Renamer::apply(target, &name, dry_run)
+  }
+
+  fn apply(target: &Path, name: &str, dry_run: bool) -> std::io::Result<String> {
+    // the name may contain separators, e.g. "2024/01/a.jpg"
+    let new_name = FileSystem::normalize_path(&target.with_file_name(name));
+    let root = target.parent().unwrap_or(Path::new(""));
+    if !FileSystem::is_within(&new_name, root) {
+      return Err(Error::new(
+        ErrorKind::InvalidInput,
+        format!("{} is outside {}", new_name.display(), root.display()),
+      ));
+    }
+
+    if !dry_run {
+      let created_dirs = FileSystem::create_parent_dirs(&new_name)?;
+      if let Err(e) = FileSystem::rename(target, new_name.to_str().unwrap()) {
+        for dir in created_dirs.iter().rev() {
+          let _ = std::fs::remove_dir(dir);
+        }
+        return Err(e);
+      }
+    }
+    Ok(new_name.to_str().unwrap().to_string())
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use crate::normalize::NormalizationForm;
+  use crate::transliterate::Transliterate;
   use std::{fs::File, io::Write, path::Path};
 
   fn create_file(path: &Path) -> bool {
@@ -142,4 +331,157 @@ mod tests {
     assert!(delete_file(target));
     assert!(!exists_file(target));
   }
+
+  #[test]
+  fn test_normalize_1() {
+    let mut renamer = super::Renamer::new();
+    // "test_renamer_3_" + "ガ" (NFD)
+    let target = Path::new("test_renamer_3_\u{30AB}\u{3099}.txt");
+    let expected_name = Path::new("test_renamer_3_\u{30AC}.txt");
+
+    assert!(create_file(target));
+    assert!(
+      renamer
+        .normalize(target, NormalizationForm::Nfc, false)
+        .ok()
+        .unwrap()
+        == expected_name.to_str().unwrap()
+    );
+    assert!(!exists_file(target));
+    assert!(exists_file(expected_name));
+    assert!(delete_file(expected_name));
+  }
+
+  #[test]
+  fn test_normalize_collision_1() {
+    let mut renamer = super::Renamer::new();
+    let target = Path::new("test_renamer_4_\u{30AB}\u{3099}.txt");
+    let twin = Path::new("test_renamer_4_\u{30AC}.txt");
+
+    assert!(create_file(target));
+    assert!(create_file(twin));
+    assert!(
+      renamer
+        .normalize(target, NormalizationForm::Nfc, true)
+        .is_err()
+    );
+    assert!(exists_file(target));
+    assert!(exists_file(twin));
+    assert!(delete_file(target));
+    assert!(delete_file(twin));
+  }
+
+  #[test]
+  fn test_transform_1() {
+    let mut renamer = super::Renamer::new();
+    let target = Path::new("test_renamer_5_ねこ.txt");
+    let expected_name = Path::new("test_renamer_5_neko.txt");
+
+    assert!(create_file(target));
+    assert!(
+      renamer
+        .transform(target, Transliterate::kana_to_romaji, false)
+        .ok()
+        .unwrap()
+        == expected_name.to_str().unwrap()
+    );
+    assert!(!exists_file(target));
+    assert!(exists_file(expected_name));
+    assert!(delete_file(expected_name));
+  }
+
+  #[test]
+  fn test_rename_all_1() {
+    let mut renamer = super::Renamer::new();
+    let targets = vec![
+      Path::new("test_renamer_6_a.txt"),
+      Path::new("test_renamer_6_b.txt"),
+    ];
+    let name_pattern = "\\0.bak";
+
+    for target in &targets {
+      assert!(create_file(target));
+    }
+
+    let new_names = renamer.rename_all(&targets, name_pattern, false).unwrap();
+
+    for (target, new_name) in targets.iter().zip(new_names.iter()) {
+      assert!(!exists_file(target));
+      assert!(exists_file(Path::new(new_name)));
+      assert!(delete_file(Path::new(new_name)));
+    }
+  }
+
+  #[test]
+  fn test_rename_all_rollback_1() {
+    let mut renamer = super::Renamer::new();
+    let targets = vec![
+      Path::new("test_renamer_7_a.txt"),
+      Path::new("test_renamer_7_b.txt"),
+      Path::new("test_renamer_7_c.txt"),
+    ];
+    let name_pattern = "\\0.bak";
+    // the 3rd rename fails
+    let conflict = Path::new("test_renamer_7_c.txt.bak");
+
+    for target in &targets {
+      assert!(create_file(target));
+    }
+    assert!(create_file(conflict));
+
+    assert!(renamer.rename_all(&targets, name_pattern, false).is_err());
+
+    for target in &targets {
+      assert!(exists_file(target));
+      assert!(delete_file(target));
+    }
+    assert!(!exists_file(Path::new("test_renamer_7_a.txt.bak")));
+    assert!(!exists_file(Path::new("test_renamer_7_b.txt.bak")));
+    assert!(delete_file(conflict));
+  }
+
+  #[test]
+  fn test_rename_subdirectory_1() {
+    let mut renamer = super::Renamer::new();
+    let target = Path::new("test_renamer_8.txt");
+    let expected_name = Path::new("test_renamer_8")
+      .join("sub")
+      .join("test_renamer_8.txt");
+
+    assert!(create_file(target));
+    assert_eq!(
+      expected_name.to_str().unwrap(),
+      renamer
+        .rename(target, "test_renamer_8/sub/\\0", false)
+        .unwrap()
+    );
+    assert!(!exists_file(target));
+    assert!(exists_file(&expected_name));
+    assert!(std::fs::remove_dir_all("test_renamer_8").is_ok());
+  }
+
+  #[test]
+  fn test_rename_outside_1() {
+    let mut renamer = super::Renamer::new();
+    let target = Path::new("test_renamer_9.txt");
+
+    assert!(create_file(target));
+    assert!(renamer.rename(target, "../\\0", false).is_err());
+    assert!(exists_file(target));
+    assert!(delete_file(target));
+  }
+
+  #[test]
+  fn test_rename_subdirectory_failed_1() {
+    let mut renamer = super::Renamer::new();
+    // the target does not exist, so the rename fails
+    let target = Path::new("test_renamer_10.txt");
+
+    assert!(
+      renamer
+        .rename(target, "test_renamer_10/sub/\\0", false)
+        .is_err()
+    );
+    assert!(!Path::new("test_renamer_10").exists());
+  }
 }
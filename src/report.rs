@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::RenameEvent;
+use crate::tasks::context::RenameEntry;
+use crate::tasks::rename::RenameJournal;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+  /// new name computed, but not renamed (dry run)
+  Planned,
+  Renamed,
+  Skipped,
+  /// new name rejected before renaming
+  Conflict,
+  Error,
+  /// renamed, then restored because a later rename failed
+  RolledBack,
+  /// not renamed because the series stopped before it
+  NotAttempted,
+}
+
+///
+/// Result of one target, written as one line of JSON Lines.
+/// Every field is always written (`null` if not applicable), so the schema stays stable.
+///
+/// # Examples
+/// ```
+/// use nae::event::RenameEvent;
+/// use nae::report::{ReportRecord, ReportStatus};
+/// use std::{path::PathBuf, time::Duration};
+///
+/// let record = ReportRecord::from_event(&RenameEvent::Renamed {
+///   src: PathBuf::from("a.txt"),
+///   dest: PathBuf::from("b.txt"),
+///   elapsed: Duration::from_micros(120),
+/// })
+/// .unwrap();
+/// assert_eq!(record.status, ReportStatus::Renamed);
+/// assert_eq!(
+///   record.to_json_line(),
+///   r#"{"src":"a.txt","dest":"b.txt","status":"renamed","code":null,"message":null,"elapsed_us":120}"#
+/// );
+/// ```
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReportRecord {
+  pub src: PathBuf,
+  pub dest: Option<PathBuf>,
+  pub status: ReportStatus,
+  /// one of `event::code`
+  pub code: Option<String>,
+  pub message: Option<String>,
+  /// time taken by the rename in microseconds, `None` if not renamed
+  pub elapsed_us: Option<u64>,
+}
+
+impl ReportRecord {
+  pub fn new(src: PathBuf, dest: Option<PathBuf>, status: ReportStatus) -> Self {
+    Self {
+      src,
+      dest,
+      status,
+      code: None,
+      message: None,
+      elapsed_us: None,
+    }
+  }
+
+  ///
+  /// Convert an event to a record.
+  ///
+  /// # Return
+  /// * `Option<ReportRecord>` - record, or None if the event is not a result of a target (e.g. `Scanned`)
+  ///
+  pub fn from_event(event: &RenameEvent) -> Option<Self> {
+    match event {
+      RenameEvent::Scanned { .. } | RenameEvent::Planned { .. } => None,
+      RenameEvent::Renamed { src, dest, elapsed } => {
+        let mut r = Self::new(src.clone(), Some(dest.clone()), ReportStatus::Renamed);
+        r.elapsed_us = Some(elapsed.as_micros() as u64);
+        Some(r)
+      }
+      RenameEvent::Skipped { path, reason } => {
+        let mut r = Self::new(path.clone(), None, ReportStatus::Skipped);
+        r.message = Some(reason.clone());
+        Some(r)
+      }
+      RenameEvent::Conflict {
+        path,
+        dest,
+        code,
+        message,
+      } => {
+        let mut r = Self::new(path.clone(), dest.clone(), ReportStatus::Conflict);
+        r.code = Some(code.clone());
+        r.message = Some(message.clone());
+        Some(r)
+      }
+      RenameEvent::Failed {
+        path,
+        dest,
+        code,
+        message,
+        elapsed,
+      } => {
+        let mut r = Self::new(path.clone(), dest.clone(), ReportStatus::Error);
+        r.code = Some(code.clone());
+        r.message = Some(message.clone());
+        r.elapsed_us = Some(elapsed.as_micros() as u64);
+        Some(r)
+      }
+    }
+  }
+
+  ///
+  /// Make the records of a series match the files on disk.
+  /// Renames restored by the rollback of the series become `RolledBack`,
+  /// and entries with a new name but no record are added as `NotAttempted`.
+  ///
+  /// # Arguments
+  /// * `records` - records of the events, in order
+  /// * `entries` - entries of the series
+  /// * `rollback` - journal left after the rollback (the renames not restored), `None` if not rolled back
+  ///
+  pub fn settle(
+    records: &mut Vec<ReportRecord>,
+    entries: &[RenameEntry],
+    rollback: Option<&RenameJournal>,
+  ) {
+    if let Some(journal) = rollback {
+      let kept: HashSet<&PathBuf> = journal
+        .renamed
+        .iter()
+        .flat_map(|e| match e.swapped {
+          true => vec![&e.src, &e.dest],
+          false => vec![&e.src],
+        })
+        .collect();
+      for r in records.iter_mut() {
+        if r.status == ReportStatus::Renamed && !kept.contains(&r.src) {
+          r.status = ReportStatus::RolledBack;
+        }
+      }
+    }
+
+    let reported: HashSet<PathBuf> = records.iter().map(|r| r.src.clone()).collect();
+    for entry in entries {
+      if let Some(dest) = entry.dest.as_ref()
+        && *dest != entry.src
+        && !reported.contains(&entry.src)
+      {
+        records.push(Self::new(
+          entry.src.clone(),
+          Some(dest.clone()),
+          ReportStatus::NotAttempted,
+        ));
+      }
+    }
+  }
+
+  pub fn to_json_line(&self) -> String {
+    serde_json::to_string(self).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ReportRecord, ReportStatus};
+  use crate::event::RenameEvent;
+  use crate::tasks::context::RenameEntry;
+  use crate::tasks::rename::RenameJournal;
+  use std::{path::PathBuf, time::Duration};
+
+  #[test]
+  fn test_from_event_1() {
+    let e = RenameEvent::Scanned {
+      path: PathBuf::from("a.txt"),
+    };
+    assert!(ReportRecord::from_event(&e).is_none());
+
+    let e = RenameEvent::Failed {
+      path: PathBuf::from("a.txt"),
+      dest: Some(PathBuf::from("b.txt")),
+      code: "permission_denied".to_string(),
+      message: "a.txt: Permission denied".to_string(),
+      elapsed: Duration::from_millis(2),
+    };
+    let r = ReportRecord::from_event(&e).unwrap();
+    assert_eq!(ReportStatus::Error, r.status);
+    assert_eq!(Some("permission_denied".to_string()), r.code);
+    assert_eq!(Some(2000), r.elapsed_us);
+  }
+
+  #[test]
+  fn test_to_json_line_1() {
+    let e = RenameEvent::Conflict {
+      path: PathBuf::from("a.txt"),
+      dest: Some(PathBuf::from("x.txt")),
+      code: "duplicated".to_string(),
+      message: "duplicated".to_string(),
+    };
+    let line = ReportRecord::from_event(&e).unwrap().to_json_line();
+    assert_eq!(
+      r#"{"src":"a.txt","dest":"x.txt","status":"conflict","code":"duplicated","message":"duplicated","elapsed_us":null}"#,
+      line
+    );
+
+    let r: ReportRecord = serde_json::from_str(&line).unwrap();
+    assert_eq!(ReportStatus::Conflict, r.status);
+  }
+
+  #[test]
+  fn test_settle_1() {
+    // "a" -> "p" renamed, "b" -> "c" failed and rolled back, "c" -> "q" not attempted
+    let entries: Vec<RenameEntry> = [("a", "p"), ("b", "c"), ("c", "q"), ("d", "d")]
+      .iter()
+      .map(|(src, dest)| {
+        let mut e = RenameEntry::new(&PathBuf::from(src));
+        e.dest = Some(PathBuf::from(dest));
+        e
+      })
+      .collect();
+    let mut records = vec![
+      ReportRecord::new(
+        PathBuf::from("a"),
+        Some(PathBuf::from("p")),
+        ReportStatus::Renamed,
+      ),
+      ReportRecord::new(
+        PathBuf::from("b"),
+        Some(PathBuf::from("c")),
+        ReportStatus::Error,
+      ),
+    ];
+
+    let journal = RenameJournal::default();
+    ReportRecord::settle(&mut records, &entries, Some(&journal));
+    let statuses: Vec<(&str, ReportStatus)> = records
+      .iter()
+      .map(|r| (r.src.to_str().unwrap(), r.status))
+      .collect();
+    assert_eq!(
+      vec![
+        ("a", ReportStatus::RolledBack),
+        ("b", ReportStatus::Error),
+        ("c", ReportStatus::NotAttempted)
+      ],
+      statuses
+    );
+    assert!(
+      records[2]
+        .to_json_line()
+        .contains(r#""status":"not_attempted""#)
+    );
+  }
+}
@@ -0,0 +1,288 @@
+use std::{fs, path::Path};
+
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::Deserialize;
+
+///
+/// One substitution of a rules file.
+///
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+  /// text to find, a regex if `regex` is set
+  pub find: String,
+  /// replacement, may refer to groups (e.g. `$1`) if `regex` is set
+  #[serde(default)]
+  pub replace: String,
+  #[serde(default)]
+  pub regex: bool,
+  #[serde(default)]
+  pub ignore_case: bool,
+  /// replace only the first match
+  #[serde(default)]
+  pub first_only: bool,
+  /// match only whole words, not next to a letter, digit or '_',
+  /// e.g. "scan" in "scan 01" but not in "scanned"
+  #[serde(default)]
+  pub whole_word: bool,
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+  #[serde(default)]
+  rules: Vec<Rule>,
+}
+
+///
+/// Ordered list of substitutions applied to file names, loaded from a TOML or JSON file.
+///
+/// # Examples
+/// ```
+/// use nae::rules::RuleSet;
+///
+/// let rules = RuleSet::from_toml(r#"
+/// [[rules]]
+/// find = "scan"
+/// replace = "Scan"
+/// ignore_case = true
+/// whole_word = true
+///
+/// [[rules]]
+/// find = '\s*\((\d+)\)'
+/// replace = "_$1"
+/// regex = true
+/// "#).unwrap();
+/// assert_eq!(rules.apply("SCAN scanned (2).pdf"), "Scan scanned_2.pdf");
+/// ```
+///
+#[derive(Debug)]
+pub struct RuleSet {
+  rules: Vec<(Rule, Regex)>,
+}
+
+impl RuleSet {
+  pub fn new(rules: Vec<Rule>) -> Result<Self, String> {
+    let mut compiled = vec![];
+
+    for (i, rule) in rules.into_iter().enumerate() {
+      // whole words are checked in apply(), "\b" fails next to punctuation, e.g. "(1)"
+      let pattern = if rule.regex {
+        rule.find.clone()
+      } else {
+        regex::escape(&rule.find)
+      };
+
+      match RegexBuilder::new(&pattern)
+        .case_insensitive(rule.ignore_case)
+        .build()
+      {
+        Ok(regex) => compiled.push((rule, regex)),
+        Err(e) => return Err(format!("rule {}: {}", i + 1, e)),
+      }
+    }
+
+    Ok(Self { rules: compiled })
+  }
+
+  ///
+  /// Load rules from a file, TOML or JSON by its extension.
+  ///
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let s = match fs::read_to_string(path) {
+      Ok(s) => s,
+      Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    let ret = match path.extension().and_then(|e| e.to_str()) {
+      Some("toml") => RuleSet::from_toml(&s),
+      Some("json") => RuleSet::from_json(&s),
+      _ => Err("unknown format, use .toml or .json".to_string()),
+    };
+    ret.map_err(|e| format!("{}: {}", path.display(), e))
+  }
+
+  pub fn from_toml(s: &str) -> Result<Self, String> {
+    match toml::from_str::<RulesFile>(s) {
+      Ok(file) => RuleSet::new(file.rules),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  pub fn from_json(s: &str) -> Result<Self, String> {
+    match serde_json::from_str::<RulesFile>(s) {
+      Ok(file) => RuleSet::new(file.rules),
+      Err(e) => Err(e.to_string()),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.rules.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  ///
+  /// Apply the rules in order, each to the result of the previous one.
+  ///
+  pub fn apply(&self, name: &str) -> String {
+    let mut ret = name.to_string();
+
+    for (rule, regex) in &self.rules {
+      let limit = if rule.first_only { 1 } else { 0 };
+      ret = if rule.whole_word {
+        RuleSet::replace_words(rule, regex, &ret)
+      } else if rule.regex {
+        regex
+          .replacen(&ret, limit, rule.replace.as_str())
+          .to_string()
+      } else {
+        regex
+          .replacen(&ret, limit, NoExpand(&rule.replace))
+          .to_string()
+      };
+    }
+
+    ret
+  }
+
+  /// replace the matches not next to a letter, digit or '_'
+  fn replace_words(rule: &Rule, regex: &Regex, s: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    // the next character boundary after `i`
+    let next = |i: usize| i + s[i..].chars().next().map_or(1, |c| c.len_utf8());
+
+    let mut ret = String::new();
+    let mut last = 0;
+    let mut pos = 0;
+    while pos <= s.len() {
+      let Some(caps) = regex.captures_at(s, pos) else {
+        break;
+      };
+      let m = caps.get(0).unwrap();
+      if s[..m.start()].chars().next_back().is_some_and(is_word)
+        || s[m.end()..].chars().next().is_some_and(is_word)
+      {
+        // a later match may start inside this one, e.g. "a" in "aa a"
+        pos = next(m.start());
+        continue;
+      }
+
+      ret.push_str(&s[last..m.start()]);
+      if rule.regex {
+        caps.expand(&rule.replace, &mut ret);
+      } else {
+        ret.push_str(&rule.replace);
+      }
+      last = m.end();
+      if rule.first_only {
+        break;
+      }
+      pos = if m.is_empty() { next(m.end()) } else { m.end() };
+    }
+
+    ret.push_str(&s[last..]);
+    ret
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Rule, RuleSet};
+  use std::{fs, path::Path};
+
+  fn rule(find: &str, replace: &str) -> Rule {
+    Rule {
+      find: find.to_string(),
+      replace: replace.to_string(),
+      regex: false,
+      ignore_case: false,
+      first_only: false,
+      whole_word: false,
+    }
+  }
+
+  #[test]
+  fn test_apply_1() {
+    let rules = RuleSet::new(vec![rule("_", " "), rule("  ", " ")]).unwrap();
+    assert_eq!("a b c.txt", rules.apply("a__b_c.txt"));
+
+    // "$" is literal
+    let rules = RuleSet::new(vec![rule("USD", "$1")]).unwrap();
+    assert_eq!("$1 price.txt", rules.apply("USD price.txt"));
+  }
+
+  #[test]
+  fn test_apply_flags_1() {
+    let mut r = rule("img", "photo");
+    r.ignore_case = true;
+    r.first_only = true;
+    let rules = RuleSet::new(vec![r]).unwrap();
+    assert_eq!("photo_IMG.jpg", rules.apply("IMG_IMG.jpg"));
+
+    let mut r = rule("a", "an");
+    r.whole_word = true;
+    let rules = RuleSet::new(vec![r]).unwrap();
+    assert_eq!("an apple and an.txt", rules.apply("a apple and a.txt"));
+  }
+
+  #[test]
+  fn test_apply_whole_word_1() {
+    let mut paren = rule("(1)", "");
+    paren.whole_word = true;
+    let mut draft = rule("-draft", "");
+    draft.whole_word = true;
+    let rules = RuleSet::new(vec![paren, draft]).unwrap();
+    assert_eq!("a .txt", rules.apply("a (1).txt"));
+    assert_eq!("a(1).txt", rules.apply("a(1).txt"));
+    assert_eq!("memo .txt", rules.apply("memo -draft.txt"));
+    assert_eq!("memo -drafts.txt", rules.apply("memo -drafts.txt"));
+
+    let mut r = rule(r"v(\d)", "V$1");
+    r.regex = true;
+    r.whole_word = true;
+    let rules = RuleSet::new(vec![r]).unwrap();
+    assert_eq!("V2 xv3 V4.txt", rules.apply("v2 xv3 v4.txt"));
+
+    let mut r = rule("a", "b");
+    r.whole_word = true;
+    r.first_only = true;
+    let rules = RuleSet::new(vec![r]).unwrap();
+    assert_eq!("aa b a", rules.apply("aa a a"));
+  }
+
+  #[test]
+  fn test_from_json_1() {
+    let rules = RuleSet::from_json(
+      r#"{"rules": [{"find": "(\\d{4})(\\d{2})(\\d{2})", "replace": "$1-$2-$3", "regex": true}]}"#,
+    )
+    .unwrap();
+    assert_eq!(1, rules.len());
+    assert_eq!("scan 2024-05-01.pdf", rules.apply("scan 20240501.pdf"));
+  }
+
+  #[test]
+  fn test_invalid_1() {
+    let mut r = rule("(", "");
+    assert!(RuleSet::new(vec![r.clone()]).is_ok());
+    r.regex = true;
+    assert!(RuleSet::new(vec![r]).unwrap_err().starts_with("rule 1:"));
+
+    assert!(RuleSet::from_toml("[[rules]]\nreplace = \"x\"").is_err());
+  }
+
+  #[test]
+  fn test_load_1() {
+    let dir = Path::new("test_rules_1");
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join("rules.toml");
+    fs::write(&path, "[[rules]]\nfind = \" \"\nreplace = \"_\"\n").unwrap();
+
+    let rules = RuleSet::load(&path);
+    let yaml = RuleSet::load(&dir.join("rules.yaml"));
+    fs::remove_dir_all(dir).unwrap();
+
+    assert_eq!("a_b.txt", rules.unwrap().apply("a b.txt"));
+    assert!(yaml.is_err());
+  }
+}
@@ -0,0 +1,286 @@
+use std::path::{Path, PathBuf};
+
+use crate::filesystem::FileSystem;
+use crate::moves::Moves;
+use crate::plan::Plan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptFormat {
+  /// POSIX shell, `mv -n`
+  Sh,
+  /// PowerShell, `Move-Item`
+  PowerShell,
+}
+
+impl ScriptFormat {
+  ///
+  /// Format by the extension, PowerShell for ".ps1" and POSIX shell otherwise.
+  ///
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension().and_then(|e| e.to_str()) {
+      Some(e) if e.eq_ignore_ascii_case("ps1") => ScriptFormat::PowerShell,
+      _ => ScriptFormat::Sh,
+    }
+  }
+}
+
+///
+/// Shell scripts doing the renames of a `Plan`, to review them or to run them without nae.
+///
+/// # Examples
+/// ```
+/// use nae::plan::Plan;
+/// use nae::script::{Script, ScriptFormat};
+/// use std::path::Path;
+///
+/// let mut plan = Plan::new(Path::new("."));
+/// plan.add(Path::new("Cargo.toml"), &"Cargo.toml.bak".to_string()).unwrap();
+/// let script = Script::render(&plan, ScriptFormat::Sh, false);
+/// assert!(script.contains("mv -n -- 'Cargo.toml' 'Cargo.toml.bak'\n"));
+/// ```
+///
+pub struct Script {}
+
+impl Script {
+  ///
+  /// Render the plan as a script.
+  ///
+  /// # Arguments
+  /// * `plan` - renames
+  /// * `format` - shell of the script
+  /// * `undo` - rename the new names back to the old ones
+  ///
+  pub fn render(plan: &Plan, format: ScriptFormat, undo: bool) -> String {
+    let moves: Vec<(String, String)> = plan
+      .get_plans()
+      .iter()
+      .map(|e| {
+        let (src, dest) = (e.get_source().to_string(), e.get_destination().to_string());
+        if undo { (dest, src) } else { (src, dest) }
+      })
+      .collect();
+    let root = plan.get_root().get_path();
+
+    let mut ret = String::new();
+    match format {
+      ScriptFormat::Sh => {
+        ret.push_str("#!/bin/sh\n");
+        ret.push_str("# generated by nae, -n never overwrites existing files\n");
+        ret.push_str("set -eu\n");
+        ret.push_str(&format!("cd -- {}\n", Script::quote(root, format)));
+      }
+      ScriptFormat::PowerShell => {
+        ret.push_str("# generated by nae, Move-Item never overwrites existing files\n");
+        ret.push_str("$ErrorActionPreference = 'Stop'\n");
+        ret.push_str(&format!(
+          "Set-Location -LiteralPath {}\n",
+          Script::quote(root, format)
+        ));
+      }
+    }
+
+    for (src, dest) in Script::order(moves) {
+      if let Some(dir) = Path::new(&dest)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+      {
+        let dir = Script::quote(&dir.to_string_lossy(), format);
+        ret.push_str(&match format {
+          ScriptFormat::Sh => format!("mkdir -p -- {}\n", dir),
+          ScriptFormat::PowerShell => {
+            format!(
+              "New-Item -ItemType Directory -Force -Path {} | Out-Null\n",
+              dir
+            )
+          }
+        });
+      }
+
+      let (src, dest) = (Script::quote(&src, format), Script::quote(&dest, format));
+      ret.push_str(&match format {
+        ScriptFormat::Sh => format!("mv -n -- {} {}\n", src, dest),
+        ScriptFormat::PowerShell => {
+          format!("Move-Item -LiteralPath {} -Destination {}\n", src, dest)
+        }
+      });
+    }
+
+    ret
+  }
+
+  ///
+  /// Path of the undo script, e.g. "rename.undo.sh" for "rename.sh".
+  ///
+  pub fn undo_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+      Some(ext) => path.with_file_name(format!("{}.undo.{}", stem, ext.to_string_lossy())),
+      None => path.with_file_name(format!("{}.undo", stem)),
+    }
+  }
+
+  ///
+  /// Order moves so no file is moved onto another one still to be moved.
+  /// Cycles (e.g. a -> b, b -> a) and case-only changes go through temporary names.
+  ///
+  fn order(moves: Vec<(String, String)>) -> Vec<(String, String)> {
+    let mut temporary = |name: &String| {
+      FileSystem::temporary_path(Path::new(name))
+        .to_string_lossy()
+        .to_string()
+    };
+
+    let mut ret = vec![];
+    for (src, dest) in Moves::order(moves, &mut temporary) {
+      // the same file on case-insensitive file systems, "mv -n" would skip it
+      if src.to_lowercase() == dest.to_lowercase() {
+        let tmp = temporary(&src);
+        ret.push((src, tmp.clone()));
+        ret.push((tmp, dest));
+      } else {
+        ret.push((src, dest));
+      }
+    }
+
+    ret
+  }
+
+  /// quote for the shell, e.g. "it's" -> 'it'\''s' in POSIX shell
+  fn quote(s: &str, format: ScriptFormat) -> String {
+    match format {
+      ScriptFormat::Sh => format!("'{}'", s.replace('\'', "'\\''")),
+      // PowerShell also treats typographic quotes as quotes
+      ScriptFormat::PowerShell => {
+        let mut ret = String::from("'");
+        for c in s.chars() {
+          if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            ret.push(c);
+          }
+          ret.push(c);
+        }
+        ret.push('\'');
+        ret
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Script, ScriptFormat};
+  use crate::filesystem::FileSystem;
+  use crate::plan::Plan;
+  use crate::tasks::context::RenameEntry;
+  use std::{
+    fs,
+    path::{Path, PathBuf},
+  };
+
+  fn moves(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+      .iter()
+      .map(|(a, b)| (a.to_string(), b.to_string()))
+      .collect()
+  }
+
+  fn temporary(name: &str) -> String {
+    FileSystem::temporary_path(Path::new(name))
+      .to_string_lossy()
+      .to_string()
+  }
+
+  #[test]
+  fn test_order_chain_1() {
+    // b must be moved out of the way before a
+    let r = Script::order(moves(&[("a", "b"), ("b", "c")]));
+    assert_eq!(moves(&[("b", "c"), ("a", "b")]), r);
+  }
+
+  #[test]
+  fn test_order_cycle_1() {
+    let r = Script::order(moves(&[("a", "b"), ("b", "a"), ("c", "c")]));
+    let tmp = temporary("a");
+    assert_eq!(moves(&[("a", &tmp), ("b", "a"), (&tmp, "b")]), r);
+  }
+
+  #[test]
+  fn test_order_case_only_1() {
+    let r = Script::order(moves(&[("dir/a.txt", "dir/A.txt")]));
+    let tmp = temporary("dir/a.txt");
+    assert_eq!(moves(&[("dir/a.txt", &tmp), (&tmp, "dir/A.txt")]), r);
+  }
+
+  #[test]
+  fn test_render_1() {
+    let mut plan = Plan::new(Path::new("."));
+    plan
+      .add(Path::new("README.md"), &"docs/README.md".to_string())
+      .unwrap();
+
+    let script = Script::render(&plan, ScriptFormat::Sh, false);
+    let lines: Vec<&str> = script.lines().collect();
+    assert_eq!("#!/bin/sh", lines[0]);
+    assert!(lines[3].starts_with("cd -- '/"));
+    assert_eq!(
+      vec![
+        "mkdir -p -- 'docs'",
+        "mv -n -- 'README.md' 'docs/README.md'"
+      ],
+      lines[4..]
+    );
+
+    let undo = Script::render(&plan, ScriptFormat::PowerShell, true);
+    assert!(undo.ends_with("Move-Item -LiteralPath 'docs/README.md' -Destination 'README.md'\n"));
+  }
+
+  #[test]
+  fn test_render_nested_1() {
+    let dir = Path::new("test_script_nested_1");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.jpg"), "").unwrap();
+    fs::write(dir.join("sub").join("a.jpg"), "").unwrap();
+
+    let mut entry = RenameEntry::new(&dir.join("sub").join("a.jpg"));
+    entry.dest = Some(dir.join("sub").join("x_a.jpg"));
+    let plan = Plan::from_entries(dir, &[entry]);
+    fs::remove_dir_all(dir).unwrap();
+    let plan = plan.unwrap();
+
+    let script = Script::render(&plan, ScriptFormat::Sh, false);
+    assert!(script.ends_with("mkdir -p -- 'sub'\nmv -n -- 'sub/a.jpg' 'sub/x_a.jpg'\n"));
+    let undo = Script::render(&plan, ScriptFormat::Sh, true);
+    assert!(undo.ends_with("mv -n -- 'sub/x_a.jpg' 'sub/a.jpg'\n"));
+  }
+
+  #[test]
+  fn test_quote_1() {
+    assert_eq!(
+      "'it'\\''s $HOME'",
+      Script::quote("it's $HOME", ScriptFormat::Sh)
+    );
+    assert_eq!(
+      "'it''s $HOME'",
+      Script::quote("it's $HOME", ScriptFormat::PowerShell)
+    );
+    assert_eq!(
+      "'a\u{2019}\u{2019}b'",
+      Script::quote("a\u{2019}b", ScriptFormat::PowerShell)
+    );
+  }
+
+  #[test]
+  fn test_undo_path_1() {
+    assert_eq!(
+      PathBuf::from("out/rename.undo.sh"),
+      Script::undo_path(Path::new("out/rename.sh"))
+    );
+    assert_eq!(
+      PathBuf::from("rename.undo"),
+      Script::undo_path(Path::new("rename"))
+    );
+    assert_eq!(
+      ScriptFormat::PowerShell,
+      ScriptFormat::from_path(Path::new("rename.PS1"))
+    );
+  }
+}
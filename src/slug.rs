@@ -0,0 +1,184 @@
+use crate::extension::Extension;
+
+pub struct Slug {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlugOptions {
+  /// character unsafe characters are replaced with, `None` to strip them
+  pub replacement: Option<char>,
+  /// characters kept even if they are unsafe, e.g. "~+"
+  pub allow: String,
+}
+
+impl Default for SlugOptions {
+  fn default() -> Self {
+    Self {
+      replacement: Some('-'),
+      allow: String::new(),
+    }
+  }
+}
+
+/// characters with a meaning in shells
+const SHELL_CHARS: &str = "`~!#$&*()|\\;'\"<>?[]{}^";
+/// reserved characters of URLs (RFC 3986), and `%` for escapes
+const URL_CHARS: &str = ":/?#[]@!$&'()*+,;=%";
+
+impl Slug {
+  ///
+  /// Make a name safe for shells and URLs.
+  /// Whitespace, control characters, shell metacharacters, emoji and URL-reserved characters are replaced,
+  /// repeated separators are collapsed, and leading dashes and dots are trimmed from the name before the extension.
+  /// A name left empty becomes the replacement character (`_` if it is a dash or a dot).
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::slug::{Slug, SlugOptions};
+  ///
+  /// let options = SlugOptions::default();
+  /// assert_eq!(Slug::apply("My Report (final) #2.pdf", &options), "My-Report-final-2.pdf");
+  /// assert_eq!(Slug::apply("--rm -rf.sh", &options), "rm-rf.sh");
+  /// ```
+  ///
+  pub fn apply(s: &str, options: &SlugOptions) -> String {
+    let mut ret = String::new();
+
+    for c in s.chars() {
+      if options.allow.contains(c) || !Slug::is_unsafe(c) {
+        ret.push(c);
+      } else if Slug::is_invisible(c) {
+        // parts of emoji sequences, e.g. ZWJ and variation selectors
+        continue;
+      } else if let Some(r) = options.replacement {
+        ret.push(r);
+      }
+    }
+
+    let ret = Slug::collapse_separators(&ret, options);
+
+    // "-a-.txt" -> "a.txt", the extension is kept
+    let (stem, ext) = Extension::split(&ret);
+    let stem = stem
+      .trim_start_matches(['-', '.'])
+      .trim_end_matches(|c| Slug::is_separator(c, options));
+    let ext = ext.trim_end_matches(|c| Slug::is_separator(c, options));
+    let ext = if ext == "." { "" } else { ext };
+
+    if stem.is_empty() {
+      let replacement = match options.replacement {
+        Some(r) if r != '-' && r != '.' => r,
+        _ => '_',
+      };
+      return format!("{}{}", replacement, ext);
+    }
+    format!("{}{}", stem, ext)
+  }
+
+  fn is_unsafe(c: char) -> bool {
+    c.is_whitespace()
+      || c.is_control()
+      || SHELL_CHARS.contains(c)
+      || URL_CHARS.contains(c)
+      || Slug::is_emoji(c)
+      || Slug::is_invisible(c)
+  }
+
+  fn is_emoji(c: char) -> bool {
+    matches!(c,
+      // Mahjong tiles .. Symbols and Pictographs Extended-A, regional indicators
+      '\u{1F000}'..='\u{1FAFF}'
+      // Miscellaneous Symbols, Dingbats
+      | '\u{2600}'..='\u{27BF}'
+      // Miscellaneous Symbols and Arrows, e.g. ⭐
+      | '\u{2B00}'..='\u{2BFF}'
+      // keycap
+      | '\u{20E3}'
+    )
+  }
+
+  fn is_invisible(c: char) -> bool {
+    matches!(c,
+      // zero width space, ZWNJ, ZWJ
+      '\u{200B}'..='\u{200D}'
+      // variation selectors
+      | '\u{FE00}'..='\u{FE0F}'
+      | '\u{FEFF}'
+    )
+  }
+
+  fn is_separator(c: char, options: &SlugOptions) -> bool {
+    c == '-' || c == '_' || Some(c) == options.replacement
+  }
+
+  /// "a - b" -> "a-b", a run of separators is replaced with its first one
+  fn collapse_separators(s: &str, options: &SlugOptions) -> String {
+    let mut ret = String::new();
+    let mut prev_separator = false;
+
+    for c in s.chars() {
+      let separator = Slug::is_separator(c, options);
+      if !(separator && prev_separator) {
+        ret.push(c);
+      }
+      prev_separator = separator;
+    }
+
+    ret
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Slug, SlugOptions};
+
+  #[test]
+  fn test_apply_1() {
+    let options = SlugOptions::default();
+    assert_eq!("a-b.txt", Slug::apply("a b.txt", &options));
+    assert_eq!("a-b.txt", Slug::apply("a  \t b.txt", &options));
+    assert_eq!("a_b.txt", Slug::apply("a_ b.txt", &options));
+    assert_eq!("a-b-c.txt", Slug::apply("a$b;c?.txt", &options));
+    assert_eq!("hidden", Slug::apply(".hidden", &options));
+    assert_eq!("ねこ.txt", Slug::apply("ねこ.txt", &options));
+  }
+
+  #[test]
+  fn test_apply_empty_1() {
+    let options = SlugOptions::default();
+    assert_eq!("_.txt", Slug::apply("!!!.txt", &options));
+    assert_eq!("_", Slug::apply("...", &options));
+    assert_eq!("a.txt", Slug::apply("--a-.txt", &options));
+
+    let options = SlugOptions {
+      replacement: Some('~'),
+      allow: String::new(),
+    };
+    assert_eq!("~.txt", Slug::apply("(!).txt", &options));
+  }
+
+  #[test]
+  fn test_apply_emoji_1() {
+    let options = SlugOptions::default();
+    assert_eq!("party.jpg", Slug::apply("party🎉.jpg", &options));
+    // a ZWJ sequence is replaced only once
+    assert_eq!(
+      "family-photo.jpg",
+      Slug::apply("family👨‍👩‍👧photo.jpg", &options)
+    );
+  }
+
+  #[test]
+  fn test_apply_options_1() {
+    let options = SlugOptions {
+      replacement: None,
+      allow: String::new(),
+    };
+    assert_eq!("MyReport2.pdf", Slug::apply("My Report #2.pdf", &options));
+
+    let options = SlugOptions {
+      replacement: Some('_'),
+      allow: "+".to_string(),
+    };
+    assert_eq!("c++_notes.md", Slug::apply("c++ (notes).md", &options));
+  }
+}
@@ -1,52 +1,201 @@
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TaskError {
+  /// name of the failed task
+  pub task: String,
+  pub message: String,
+  /// details, e.g. every conflicting file
+  pub diagnostics: Vec<String>,
+  /// machine-readable cause, e.g. `event::code::ALREADY_EXISTS`
+  pub code: Option<String>,
+}
+
+impl TaskError {
+  pub fn new(task: &str, message: &str) -> Self {
+    Self {
+      task: task.to_string(),
+      message: message.to_string(),
+      diagnostics: vec![],
+      code: None,
+    }
+  }
+
+  pub fn with_code(mut self, code: &str) -> Self {
+    self.code = Some(code.to_string());
+    self
+  }
+}
+
+impl std::fmt::Display for TaskError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.task, self.message)?;
+    for d in &self.diagnostics {
+      write!(f, "\n  {}", d)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for TaskError {}
+
 pub trait Task<T> {
-  fn execute(&self, target: &mut T) -> bool;
+  ///
+  /// Name shown in diagnostics and progress.
+  ///
+  fn name(&self) -> &str {
+    std::any::type_name::<Self>()
+  }
+
+  fn execute(&self, target: &mut T) -> Result<(), TaskError>;
+
+  ///
+  /// Revert `execute()`, called in reverse order when a later task failed.
+  ///
+  fn undo(&self, _target: &mut T) -> Result<(), TaskError> {
+    Ok(())
+  }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Progress {
+  /// index of the task about to be executed
+  pub index: usize,
+  pub total: usize,
+  pub task: String,
+}
+
+///
+/// Pipeline of tasks sharing one target.
+///
+/// # Examples
+/// ```
+/// use nae::task::{Invoker, Task, TaskError};
+///
+/// struct Push(u32);
+/// impl Task<Vec<u32>> for Push {
+///   fn execute(&self, target: &mut Vec<u32>) -> Result<(), TaskError> {
+///     target.push(self.0);
+///     Ok(())
+///   }
+/// }
+///
+/// let mut target = vec![];
+/// {
+///   let mut invoker = Invoker::new(&mut target);
+///   invoker.add(Push(1));
+///   invoker.add(Push(2));
+///   invoker.on_progress(|p| println!("{}/{} {}", p.index + 1, p.total, p.task));
+///   assert!(invoker.execute_all().is_ok());
+/// }
+/// assert_eq!(target, vec![1, 2]);
+/// ```
+///
 pub struct Invoker<'a, T: 'a> {
   tasks: Vec<Box<dyn Task<T> + 'a>>,
   target: &'a mut T,
   current_index: usize,
+  progress: Option<ProgressCallback<'a>>,
 }
 
+type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
 impl<'a, T> Invoker<'a, T> {
-  fn new(target: &'a mut T) -> Self {
+  pub fn new(target: &'a mut T) -> Self {
     Self {
       tasks: vec![],
       target,
       current_index: 0,
+      progress: None,
     }
   }
 
-  fn get_target(&mut self) -> &mut T {
-    &mut self.target
+  pub fn get_target(&mut self) -> &mut T {
+    self.target
   }
 
-  fn add<U: Task<T> + 'a>(&mut self, task: U) {
+  pub fn add<U: Task<T> + 'a>(&mut self, task: U) {
     self.tasks.push(Box::new(task));
   }
 
-  fn execute(&mut self) -> bool {
+  ///
+  /// Set the callback called before each task is executed.
+  ///
+  pub fn on_progress<F: FnMut(&Progress) + 'a>(&mut self, callback: F) {
+    self.progress = Some(Box::new(callback));
+  }
+
+  ///
+  /// Execute the next task.
+  ///
+  /// # Return
+  /// * `Ok(true)` - the task succeeded
+  /// * `Ok(false)` - no task left
+  /// * `Err(e)` - the task failed
+  ///
+  pub fn execute(&mut self) -> Result<bool, TaskError> {
     if self.tasks.len() <= self.current_index {
       // NOP
-      return false;
+      return Ok(false);
     }
 
     let c = self.tasks.get(self.current_index).unwrap();
     let t = &mut *self.target;
 
-    if c.execute(t) {
-      self.current_index += 1;
-      return true;
+    if let Some(progress) = self.progress.as_mut() {
+      progress(&Progress {
+        index: self.current_index,
+        total: self.tasks.len(),
+        task: c.name().to_string(),
+      });
+    }
+
+    c.execute(t)?;
+    self.current_index += 1;
+
+    Ok(true)
+  }
+
+  ///
+  /// Execute all remaining tasks.
+  /// If a task fails, the tasks already executed are rolled back in reverse order.
+  ///
+  /// # Return
+  /// * `Ok(())` - all tasks succeeded
+  /// * `Err(e)` - a task failed (and the executed tasks were rolled back)
+  ///
+  pub fn execute_all(&mut self) -> Result<(), TaskError> {
+    while self.current_index < self.tasks.len() {
+      if let Err(mut e) = self.execute() {
+        if let Err(r) = self.rollback() {
+          e.diagnostics.push(format!(
+            "rollback failed: {}",
+            r.to_string().replace('\n', " ")
+          ));
+        }
+        return Err(e);
+      }
     }
 
-    false
+    Ok(())
   }
 
-  fn execute_all(&mut self) -> bool {
-    let mut result = true;
+  ///
+  /// Undo the executed tasks in reverse order.
+  /// Continues even if a task cannot be undone, and returns the first error.
+  ///
+  pub fn rollback(&mut self) -> Result<(), TaskError> {
+    let mut result = Ok(());
 
-    for _ in self.current_index..self.tasks.len() {
-      result &= self.execute();
+    while self.current_index > 0 {
+      self.current_index -= 1;
+
+      let c = self.tasks.get(self.current_index).unwrap();
+      let t = &mut *self.target;
+
+      if let Err(e) = c.undo(t)
+        && result.is_ok()
+      {
+        result = Err(e);
+      }
     }
 
     result
@@ -56,7 +205,6 @@ impl<'a, T> Invoker<'a, T> {
 #[cfg(test)]
 mod tests {
   use super::*;
-  use std::fs;
 
   struct Target {
     val: bool,
@@ -74,17 +222,55 @@ mod tests {
 
   struct UpdateTrueTask;
   impl Task<Target> for UpdateTrueTask {
-    fn execute(&self, target: &mut Target) -> bool {
+    fn execute(&self, target: &mut Target) -> Result<(), TaskError> {
       target.val = true;
-      true
+      Ok(())
     }
   }
 
   struct UpdateFalseTask;
   impl Task<Target> for UpdateFalseTask {
-    fn execute(&self, target: &mut Target) -> bool {
+    fn execute(&self, target: &mut Target) -> Result<(), TaskError> {
       target.val = false;
-      true
+      Ok(())
+    }
+  }
+
+  struct PushTask(u32);
+  impl Task<Vec<u32>> for PushTask {
+    fn name(&self) -> &str {
+      "push"
+    }
+
+    fn execute(&self, target: &mut Vec<u32>) -> Result<(), TaskError> {
+      target.push(self.0);
+      Ok(())
+    }
+
+    fn undo(&self, target: &mut Vec<u32>) -> Result<(), TaskError> {
+      match target.pop() {
+        Some(v) if v == self.0 => Ok(()),
+        _ => Err(TaskError::new(self.name(), "unexpected value")),
+      }
+    }
+  }
+
+  struct FailTask;
+  impl Task<Vec<u32>> for FailTask {
+    fn name(&self) -> &str {
+      "fail"
+    }
+
+    fn execute(&self, _target: &mut Vec<u32>) -> Result<(), TaskError> {
+      Err(TaskError::new(self.name(), "failed"))
+    }
+  }
+
+  struct CorruptTask;
+  impl Task<Vec<u32>> for CorruptTask {
+    fn execute(&self, target: &mut Vec<u32>) -> Result<(), TaskError> {
+      target.push(9);
+      Ok(())
     }
   }
 
@@ -93,14 +279,86 @@ mod tests {
     let mut target = Target::new();
     let mut invoker = Invoker::new(&mut target);
 
-    assert_eq!(false, invoker.get_target().get());
+    assert!(!invoker.get_target().get());
 
     invoker.add(UpdateTrueTask);
-    assert!(invoker.execute());
-    assert_eq!(true, invoker.get_target().get());
+    assert_eq!(Ok(true), invoker.execute());
+    assert!(invoker.get_target().get());
 
     invoker.add(UpdateFalseTask);
-    assert!(invoker.execute());
-    assert_eq!(false, invoker.get_target().get());
+    assert_eq!(Ok(true), invoker.execute());
+    assert!(!invoker.get_target().get());
+
+    assert_eq!(Ok(false), invoker.execute());
+  }
+
+  #[test]
+  fn test_execute_all_1() {
+    let mut target = vec![];
+    let mut invoker = Invoker::new(&mut target);
+
+    invoker.add(PushTask(1));
+    invoker.add(PushTask(2));
+    assert!(invoker.execute_all().is_ok());
+
+    assert_eq!(&vec![1, 2], invoker.get_target());
+  }
+
+  #[test]
+  fn test_execute_all_rollback_1() {
+    let mut target = vec![0];
+    let mut invoker = Invoker::new(&mut target);
+
+    invoker.add(PushTask(1));
+    invoker.add(PushTask(2));
+    invoker.add(FailTask);
+    invoker.add(PushTask(3));
+
+    let e = invoker.execute_all().unwrap_err();
+    assert_eq!("fail", e.task);
+    assert_eq!("failed", e.message);
+
+    assert_eq!(&vec![0], invoker.get_target());
+  }
+
+  #[test]
+  fn test_execute_all_rollback_2() {
+    let mut target = vec![0];
+    let mut invoker = Invoker::new(&mut target);
+
+    invoker.add(PushTask(1));
+    invoker.add(PushTask(2));
+    // break the undo of PushTask(2)
+    invoker.add(CorruptTask);
+    invoker.add(FailTask);
+
+    let e = invoker.execute_all().unwrap_err();
+    assert_eq!("fail", e.task);
+    assert_eq!(1, e.diagnostics.len());
+  }
+
+  #[test]
+  fn test_progress_1() {
+    let mut target = vec![];
+    let mut names = vec![];
+
+    {
+      let mut invoker = Invoker::new(&mut target);
+      invoker.add(PushTask(1));
+      invoker.add(UpdateNameless);
+      invoker.on_progress(|p| names.push(format!("{}/{} {}", p.index + 1, p.total, p.task)));
+      assert!(invoker.execute_all().is_ok());
+    }
+
+    assert_eq!(2, names.len());
+    assert_eq!("1/2 push", names[0]);
+    assert!(names[1].starts_with("2/2 ") && names[1].ends_with("UpdateNameless"));
+  }
+
+  struct UpdateNameless;
+  impl Task<Vec<u32>> for UpdateNameless {
+    fn execute(&self, _target: &mut Vec<u32>) -> Result<(), TaskError> {
+      Ok(())
+    }
   }
 }
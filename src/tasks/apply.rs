@@ -0,0 +1,503 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::event::{EventCallback, RenameEvent, code};
+use crate::filesystem::{FileSystem, Verify};
+use crate::moves::Moves;
+use crate::task::{Invoker, Task, TaskError};
+use crate::tasks::context::RenameContext;
+use crate::tasks::rename::{RenameJournal, RenameTask, SwapTask};
+
+///
+/// Rename the entries to their new names.
+/// Entries in a directory are renamed before the directory itself, and two entries renamed to each other are exchanged.
+/// An entry is renamed after the one holding its new name, longer cycles go through temporary names.
+/// If one of the renames fails, the renamed files are restored.
+/// If `RenameContext::cancel` is cancelled, stops before the next file and keeps the renamed files.
+///
+pub struct ApplyTask {
+  dry_run: bool,
+  remove_empty_dirs: bool,
+  verify: Verify,
+}
+
+impl ApplyTask {
+  pub fn new(dry_run: bool) -> Self {
+    Self {
+      dry_run,
+      remove_empty_dirs: false,
+      verify: Verify::default(),
+    }
+  }
+
+  ///
+  /// Set the check of the files copied to another file system.
+  ///
+  pub fn with_verify(mut self, verify: Verify) -> Self {
+    self.verify = verify;
+    self
+  }
+
+  ///
+  /// Remove the directories left empty by moving files out of them (up to `RenameContext::root`).
+  ///
+  pub fn with_remove_empty_dirs(mut self, enabled: bool) -> Self {
+    self.remove_empty_dirs = enabled;
+    self
+  }
+}
+
+impl Task<RenameContext> for ApplyTask {
+  fn name(&self) -> &str {
+    "apply"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    if self.dry_run {
+      return Ok(());
+    }
+
+    let RenameContext {
+      root,
+      entries,
+      journal,
+      events,
+      cancel,
+      ..
+    } = context;
+
+    let mut renames = vec![];
+    for entry in entries.iter() {
+      match entry.dest.as_ref() {
+        Some(dest) if *dest != entry.src => renames.push((entry.src.clone(), dest.clone())),
+        Some(_) => emit(
+          events,
+          RenameEvent::Skipped {
+            path: entry.src.clone(),
+            reason: "same name".to_string(),
+          },
+        ),
+        None => emit(
+          events,
+          RenameEvent::Skipped {
+            path: entry.src.clone(),
+            reason: "no new name".to_string(),
+          },
+        ),
+      }
+    }
+
+    // deepest first, a directory is renamed after its contents so their paths stay valid
+    renames.sort_by_key(|(src, _)| Reverse(src.components().count()));
+
+    // "a" -> "b" and "b" -> "a" are exchanged at once
+    let dests: HashMap<&PathBuf, &PathBuf> = renames.iter().map(|(s, d)| (s, d)).collect();
+    let mut swaps = HashSet::new();
+    let mut moves = vec![];
+    for (src, dest) in &renames {
+      if dests.get(dest) == Some(&src) {
+        if swaps.contains(dest) {
+          continue;
+        }
+        swaps.insert(src.clone());
+      }
+      moves.push((src.clone(), dest.clone()));
+    }
+
+    // the old names of the files moved aside to break cycles
+    let mut originals: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let moves = Moves::order(moves, |src: &PathBuf| {
+      let tmp = FileSystem::temporary_path(src);
+      originals.insert(tmp.clone(), src.clone());
+      tmp
+    });
+
+    let mut invoker = Invoker::new(journal);
+    for (src, dest) in &moves {
+      if swaps.contains(src) {
+        invoker.add(SwapTask::new(src, dest));
+      } else {
+        invoker.add(RenameTask::new(src, dest).with_verify(self.verify));
+      }
+    }
+
+    // files under a temporary name
+    let mut aside = 0;
+    for (src, dest) in moves {
+      // stop between files, the files already renamed are kept in the journal
+      if aside == 0 && cancel.is_cancelled() {
+        invoker.get_target().cancelled = true;
+        break;
+      }
+
+      let start = Instant::now();
+      let (path, restored) = match originals.get(&src) {
+        Some(original) => (original.clone(), true),
+        None => (src, false),
+      };
+      if let Err(mut e) = invoker.execute() {
+        emit(
+          events,
+          RenameEvent::Failed {
+            dest: dests.get(&path).map(|d| d.to_path_buf()),
+            path,
+            code: e.code.clone().unwrap_or(code::IO.to_string()),
+            message: e.message.clone(),
+            elapsed: start.elapsed(),
+          },
+        );
+        if let Err(r) = invoker.rollback() {
+          e.diagnostics.push(format!(
+            "rollback failed: {}",
+            r.to_string().replace('\n', " ")
+          ));
+        }
+        return Err(e);
+      }
+
+      let elapsed = start.elapsed();
+      if originals.contains_key(&dest) {
+        aside += 1;
+        continue;
+      }
+      if restored {
+        aside -= 1;
+      }
+      let swapped = swaps.contains(&path);
+      emit(
+        events,
+        RenameEvent::Renamed {
+          src: path.clone(),
+          dest: dest.clone(),
+          elapsed,
+        },
+      );
+      if swapped {
+        emit(
+          events,
+          RenameEvent::Renamed {
+            src: dest,
+            dest: path,
+            elapsed,
+          },
+        );
+      }
+    }
+    drop(invoker);
+
+    if self.remove_empty_dirs {
+      remove_empty_dirs(root, journal);
+    }
+
+    Ok(())
+  }
+
+  fn undo(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    while let Some(dir) = context.journal.removed_dirs.last() {
+      if let Err(e) = std::fs::create_dir_all(dir) {
+        return Err(TaskError::new(
+          self.name(),
+          &format!("{}: {}", dir.display(), e),
+        ));
+      }
+      context.journal.removed_dirs.pop();
+    }
+
+    while let Some(entry) = context.journal.renamed.last() {
+      if let Err(e) = entry.undo() {
+        return Err(TaskError::new(
+          self.name(),
+          &format!("{}: {}", entry.dest.display(), e),
+        ));
+      }
+      context.journal.renamed.pop();
+    }
+
+    Ok(())
+  }
+}
+
+/// remove the empty source directories of the renamed files, deepest first
+fn remove_empty_dirs(root: &Path, journal: &mut RenameJournal) {
+  let mut dirs: Vec<PathBuf> = journal
+    .renamed
+    .iter()
+    .filter_map(|e| e.src.parent().map(|p| p.to_path_buf()))
+    .collect();
+  dirs.sort_by(|a, b| {
+    Reverse(a.components().count())
+      .cmp(&Reverse(b.components().count()))
+      .then(a.cmp(b))
+  });
+  dirs.dedup();
+
+  for dir in dirs {
+    let mut dir = dir.as_path();
+    // strictly inside the root
+    while FileSystem::is_within(dir, root) && !FileSystem::is_within(root, dir) {
+      let empty = match dir.read_dir() {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => false,
+      };
+      if !empty || std::fs::remove_dir(dir).is_err() {
+        break;
+      }
+      journal.removed_dirs.push(dir.to_path_buf());
+
+      match dir.parent() {
+        Some(parent) => dir = parent,
+        None => break,
+      }
+    }
+  }
+}
+
+fn emit(events: &mut Option<EventCallback>, event: RenameEvent) {
+  if let Some(callback) = events.as_mut() {
+    callback(&event);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ApplyTask;
+  use crate::event::RenameEvent;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use std::{cell::RefCell, fs, path::Path, rc::Rc};
+
+  fn context(dir: &Path) -> RenameContext {
+    let mut context = RenameContext::new(dir);
+    for (src, dest) in [("a.txt", "b.txt"), ("c.txt", "d.txt")] {
+      assert!(fs::write(dir.join(src), "\n").is_ok());
+      let mut e = RenameEntry::new(&dir.join(src));
+      e.dest = Some(dir.join(dest));
+      context.entries.push(e);
+    }
+    context
+  }
+
+  #[test]
+  fn test_apply_and_undo_1() {
+    let dir = Path::new("test_apply_task_1");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = context(dir);
+
+    let task = ApplyTask::new(false);
+    assert!(task.execute(&mut context).is_ok());
+    assert!(dir.join("b.txt").exists());
+    assert!(dir.join("d.txt").exists());
+    assert_eq!(2, context.journal.renamed.len());
+
+    assert!(task.undo(&mut context).is_ok());
+    assert!(dir.join("a.txt").exists());
+    assert!(dir.join("c.txt").exists());
+    assert!(context.journal.renamed.is_empty());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_failed_1() {
+    let dir = Path::new("test_apply_task_2");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = context(dir);
+    // the 2nd rename fails
+    assert!(fs::write(dir.join("d.txt"), "\n").is_ok());
+
+    assert!(ApplyTask::new(false).execute(&mut context).is_err());
+    assert!(dir.join("a.txt").exists());
+    assert!(!dir.join("b.txt").exists());
+    assert!(context.journal.renamed.is_empty());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_dry_run_1() {
+    let dir = Path::new("test_apply_task_3");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = context(dir);
+
+    assert!(ApplyTask::new(true).execute(&mut context).is_ok());
+    assert!(dir.join("a.txt").exists());
+    assert!(context.journal.renamed.is_empty());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_events_1() {
+    let dir = Path::new("test_apply_task_4");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = context(dir);
+    // the 2nd rename fails
+    assert!(fs::write(dir.join("d.txt"), "\n").is_ok());
+
+    let events = Rc::new(RefCell::new(vec![]));
+    let e = events.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      e.borrow_mut().push(event.clone())
+    }));
+
+    assert!(ApplyTask::new(false).execute(&mut context).is_err());
+
+    let events = events.borrow();
+    assert_eq!(2, events.len());
+    assert!(matches!(&events[0], RenameEvent::Renamed { src, dest, .. }
+      if *src == dir.join("a.txt") && *dest == dir.join("b.txt")));
+    assert!(matches!(&events[1], RenameEvent::Failed { path, code, .. }
+      if *path == dir.join("c.txt") && code == "already_exists"));
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_cancel_1() {
+    let dir = Path::new("test_apply_task_5");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = context(dir);
+
+    // cancel after the 1st file
+    let cancel = context.cancel.clone();
+    context.events = Some(Box::new(move |_: &RenameEvent| cancel.cancel()));
+
+    assert!(ApplyTask::new(false).execute(&mut context).is_ok());
+    assert!(dir.join("b.txt").exists());
+    assert!(dir.join("c.txt").exists());
+    assert_eq!(1, context.journal.renamed.len());
+    assert!(context.journal.cancelled);
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_directories_1() {
+    let dir = Path::new("test_apply_task_6");
+    assert!(fs::create_dir_all(dir.join("sub")).is_ok());
+    assert!(fs::write(dir.join("sub").join("a.txt"), "\n").is_ok());
+
+    // the directory comes first, as sorted by name
+    let mut context = RenameContext::new(dir);
+    for (src, dest) in [("sub", "SUB"), ("sub/a.txt", "sub/A.txt")] {
+      let mut e = RenameEntry::new(&dir.join(src));
+      e.dest = Some(dir.join(dest));
+      context.entries.push(e);
+    }
+
+    let task = ApplyTask::new(false);
+    assert!(task.execute(&mut context).is_ok());
+    assert!(dir.join("SUB").join("A.txt").exists());
+
+    assert!(task.undo(&mut context).is_ok());
+    assert!(dir.join("sub").join("a.txt").exists());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_move_1() {
+    let dir = Path::new("test_apply_task_7");
+    assert!(fs::create_dir_all(dir.join("old").join("sub")).is_ok());
+    assert!(fs::write(dir.join("old").join("sub").join("a.jpg"), "\n").is_ok());
+
+    let mut context = RenameContext::new(dir);
+    let mut e = RenameEntry::new(&dir.join("old").join("sub").join("a.jpg"));
+    e.dest = Some(dir.join("2024").join("01").join("a.jpg"));
+    context.entries.push(e);
+
+    let task = ApplyTask::new(false).with_remove_empty_dirs(true);
+    assert!(task.execute(&mut context).is_ok());
+    assert!(dir.join("2024").join("01").join("a.jpg").exists());
+    assert!(!dir.join("old").exists());
+    assert_eq!(2, context.journal.removed_dirs.len());
+
+    assert!(task.undo(&mut context).is_ok());
+    assert!(dir.join("old").join("sub").join("a.jpg").exists());
+    assert!(!dir.join("2024").exists());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_chain_1() {
+    let dir = Path::new("test_apply_task_9");
+    assert!(fs::create_dir_all(dir).is_ok());
+    let mut context = RenameContext::new(dir);
+    // "1" -> "2" -> "3" -> "4", and "a" -> "b" -> "c" -> "a"
+    for (src, dest) in [
+      ("1", "2"),
+      ("2", "3"),
+      ("3", "4"),
+      ("a", "b"),
+      ("b", "c"),
+      ("c", "a"),
+    ] {
+      assert!(fs::write(dir.join(src), src).is_ok());
+      let mut e = RenameEntry::new(&dir.join(src));
+      e.dest = Some(dir.join(dest));
+      context.entries.push(e);
+    }
+
+    let events = Rc::new(RefCell::new(vec![]));
+    let e = events.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      e.borrow_mut().push(event.clone())
+    }));
+
+    let task = ApplyTask::new(false);
+    assert!(task.execute(&mut context).is_ok());
+    for (name, content) in [
+      ("2", "1"),
+      ("3", "2"),
+      ("4", "3"),
+      ("b", "a"),
+      ("c", "b"),
+      ("a", "c"),
+    ] {
+      assert_eq!(content, fs::read_to_string(dir.join(name)).unwrap());
+    }
+    assert!(!dir.join("1").exists());
+    // the temporary name is not reported
+    assert_eq!(6, events.borrow().len());
+    assert!(events.borrow().iter().any(
+      |event| matches!(event, RenameEvent::Renamed { src, dest, .. }
+      if *src == dir.join("a") && *dest == dir.join("b"))
+    ));
+
+    assert!(task.undo(&mut context).is_ok());
+    for name in ["1", "2", "3", "a", "b", "c"] {
+      assert_eq!(name, fs::read_to_string(dir.join(name)).unwrap());
+    }
+    assert_eq!(6, fs::read_dir(dir).unwrap().count());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_apply_swap_1() {
+    let dir = Path::new("test_apply_task_8");
+    assert!(fs::create_dir_all(dir).is_ok());
+    assert!(fs::write(dir.join("a.txt"), "a").is_ok());
+    assert!(fs::write(dir.join("b.txt"), "b").is_ok());
+
+    let mut context = RenameContext::new(dir);
+    for (src, dest) in [("a.txt", "b.txt"), ("b.txt", "a.txt")] {
+      let mut e = RenameEntry::new(&dir.join(src));
+      e.dest = Some(dir.join(dest));
+      context.entries.push(e);
+    }
+
+    let task = ApplyTask::new(false);
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!("b", fs::read_to_string(dir.join("a.txt")).unwrap());
+    assert_eq!(1, context.journal.renamed.len());
+
+    assert!(task.undo(&mut context).is_ok());
+    assert_eq!("a", fs::read_to_string(dir.join("a.txt")).unwrap());
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+}
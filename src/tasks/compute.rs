@@ -0,0 +1,449 @@
+use crate::case::{Case, CaseStyle};
+use crate::event::{RenameEvent, code};
+use crate::filesystem::FileSystem;
+use crate::normalize::{NormalizationForm, Normalizer};
+use crate::parser::{Error, OptionalData, Parser};
+use crate::rules::RuleSet;
+use crate::slug::{Slug, SlugOptions};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::{RenameContext, RenameEntry};
+use crate::truncate::{Truncate, TruncatePolicy};
+use crate::validity::{NAME_MAX, Platform, Validity};
+
+///
+/// Compute the new name of each entry from the name pattern.
+/// If `RenameContext::cancel` is cancelled, fails before the next entry.
+///
+pub struct ComputeNamesTask {
+  pattern: String,
+  /// first number and increment of `?`
+  counter: (u32, u32),
+  /// make names valid on the platform, replacing invalid characters
+  sanitize: Option<(Platform, char)>,
+  /// Unicode normalization of the new names, before the other steps
+  normalize: Option<NormalizationForm>,
+  /// substitutions applied to the new names, e.g. from a rules file
+  rules: Option<RuleSet>,
+  /// convert whole names to the case style, not only `\{0|snake}` etc.
+  case: Option<CaseStyle>,
+  /// make whole names safe for shells and URLs, not only `\{0|slug}`
+  slug: bool,
+  slug_options: SlugOptions,
+  /// shorten names longer than `NAME_MAX` on the platform
+  truncate: Option<(Platform, TruncatePolicy)>,
+}
+
+impl ComputeNamesTask {
+  pub fn new(pattern: &str) -> Self {
+    Self {
+      pattern: pattern.to_string(),
+      counter: (1, 1),
+      sanitize: None,
+      normalize: None,
+      rules: None,
+      case: None,
+      slug: false,
+      slug_options: SlugOptions::default(),
+      truncate: None,
+    }
+  }
+
+  ///
+  /// Number files from `start` by `step`.
+  ///
+  pub fn with_counter(mut self, start: u32, step: u32) -> Self {
+    self.counter = (start, step);
+    self
+  }
+
+  ///
+  /// Normalize the new names to the Unicode normalization form, e.g. NFC for names copied from macOS.
+  ///
+  pub fn with_normalize(mut self, form: Option<NormalizationForm>) -> Self {
+    self.normalize = form;
+    self
+  }
+
+  ///
+  /// Apply the substitutions to each component of the new names, before truncating them.
+  ///
+  pub fn with_rules(mut self, rules: Option<RuleSet>) -> Self {
+    self.rules = rules;
+    self
+  }
+
+  ///
+  /// Convert each component of the new names to the case style.
+  ///
+  pub fn with_case(mut self, style: Option<CaseStyle>) -> Self {
+    self.case = style;
+    self
+  }
+
+  ///
+  /// Apply `Slug` to each component of the new names.
+  ///
+  pub fn with_slug(mut self, enabled: bool) -> Self {
+    self.slug = enabled;
+    self
+  }
+
+  ///
+  /// Options of `with_slug()` and the `slug` modifier.
+  ///
+  pub fn with_slug_options(mut self, options: SlugOptions) -> Self {
+    self.slug_options = options;
+    self
+  }
+
+  ///
+  /// Sanitize each component of the new names for the platform, after truncating them.
+  ///
+  pub fn with_sanitize(mut self, platform: Platform, replacement: char) -> Self {
+    self.sanitize = Some((platform, replacement));
+    self
+  }
+
+  ///
+  /// Shorten new names too long for the platform.
+  /// The file name (`\0`) is shortened keeping its extension, so the counter and other parts of the pattern are kept.
+  ///
+  pub fn with_truncate(mut self, platform: Platform, policy: TruncatePolicy) -> Self {
+    self.truncate = Some((platform, policy));
+    self
+  }
+
+  /// apply `f` to each name in "2024/01/a.jpg", keeping "." and ".." to move to other directories
+  fn map_components<F: Fn(&str) -> String>(name: &str, f: F) -> String {
+    name
+      .split('/')
+      .map(|n| match n {
+        "" | "." | ".." => n.to_string(),
+        _ => f(n),
+      })
+      .collect::<Vec<String>>()
+      .join("/")
+  }
+
+  /// normalize, rules, case and slug, in this order, on a parsed name
+  fn transform(&self, mut name: String) -> String {
+    if let Some(form) = self.normalize {
+      name = Normalizer::normalize(&name, form);
+    }
+    if let Some(rules) = self.rules.as_ref() {
+      name = ComputeNamesTask::map_components(&name, |n| rules.apply(n));
+    }
+    if let Some(style) = self.case {
+      name = ComputeNamesTask::map_components(&name, |n| Case::convert(n, style));
+    }
+    if self.slug {
+      name = ComputeNamesTask::map_components(&name, |n| Slug::apply(n, &self.slug_options));
+    }
+    name
+  }
+
+  /// sanitize last, so no truncation leaves a trailing dot or space on Windows
+  fn sanitize(&self, name: String) -> String {
+    match self.sanitize {
+      Some((platform, replacement)) => {
+        ComputeNamesTask::map_components(&name, |n| Validity::sanitize(n, platform, replacement))
+      }
+      None => name,
+    }
+  }
+
+  /// parse and transform the new name, then truncate it as the other steps can make it longer
+  fn parse(&self, parser: &mut Parser, entry: &RenameEntry) -> Result<String, Error> {
+    let file_name = &entry.file_name();
+    let mut optional_data = OptionalData::new(file_name).with_path(&entry.src);
+    let mut name = self.transform(parser.parse(&self.pattern, Some(&optional_data))?);
+
+    let (platform, policy) = match self.truncate {
+      Some(truncate) => truncate,
+      None => return Ok(self.sanitize(name)),
+    };
+
+    let mut prev_length = usize::MAX;
+    loop {
+      let (dir, last) = name.split_at(name.rfind('/').map(|i| i + 1).unwrap_or(0));
+      let length = platform.length(last);
+      if length <= NAME_MAX {
+        return Ok(self.sanitize(name));
+      }
+
+      let current = platform.length(&optional_data.file_name);
+      // the pattern has no file name, or the rest of it is too long
+      if length >= prev_length || current <= length - NAME_MAX {
+        return Ok(self.sanitize(format!(
+          "{}{}",
+          dir,
+          Truncate::fit(last, NAME_MAX, platform, policy)
+        )));
+      }
+      prev_length = length;
+
+      optional_data.file_name =
+        Truncate::fit(file_name, current - (length - NAME_MAX), platform, policy);
+      parser.rewind();
+      name = self.transform(parser.parse(&self.pattern, Some(&optional_data))?);
+    }
+  }
+}
+
+impl Task<RenameContext> for ComputeNamesTask {
+  fn name(&self) -> &str {
+    "compute"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let mut parser = Parser::new()
+      .with_counter(self.counter.0, self.counter.1)
+      .with_slug_options(self.slug_options.clone());
+
+    for i in 0..context.entries.len() {
+      if context.cancel.is_cancelled() {
+        return Err(TaskError::new(self.name(), "cancelled").with_code(code::CANCELLED));
+      }
+      let entry = &mut context.entries[i];
+      match self.parse(&mut parser, entry) {
+        Ok(name) => {
+          // the name may contain separators, e.g. "2024/01/a.jpg"
+          let dest = FileSystem::normalize_path(&entry.src.with_file_name(name));
+          entry.dest = Some(dest.clone());
+
+          let src = entry.src.clone();
+          context.emit(RenameEvent::Planned { src, dest });
+        }
+        Err(e) => {
+          return Err(TaskError::new(
+            self.name(),
+            &format!("{} (column: {})", e.message, e.column),
+          ));
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ComputeNamesTask;
+  use crate::case::CaseStyle;
+  use crate::normalize::NormalizationForm;
+  use crate::rules::RuleSet;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use crate::truncate::TruncatePolicy;
+  use crate::validity::Platform;
+  use std::path::{Path, PathBuf};
+
+  #[test]
+  fn test_compute_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    for name in ["dir/a.jpg", "dir/b.jpg"] {
+      context.entries.push(RenameEntry::new(Path::new(name)));
+    }
+
+    let task = ComputeNamesTask::new("photo??_\\0");
+    assert!(task.execute(&mut context).is_ok());
+
+    let dests = context
+      .entries
+      .iter()
+      .map(|e| e.dest.clone().unwrap())
+      .collect::<Vec<PathBuf>>();
+    assert_eq!(
+      vec![
+        PathBuf::from("dir/photo01_a.jpg"),
+        PathBuf::from("dir/photo02_b.jpg")
+      ],
+      dests
+    );
+  }
+
+  #[test]
+  fn test_compute_sanitize_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("dir/a.jpg")));
+
+    let task = ComputeNamesTask::new("aux/12:30 \\0").with_sanitize(Platform::Windows, '-');
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(PathBuf::from("dir/aux-/12-30 a.jpg")),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_normalize_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("dir/\u{30AB}\u{3099}.txt")));
+
+    let task = ComputeNamesTask::new("\\0").with_normalize(Some(NormalizationForm::Nfc));
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(PathBuf::from("dir/\u{30AC}.txt")),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_slug_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("dir/My Photo!.jpg")));
+
+    let task = ComputeNamesTask::new("2024 05/\\0").with_slug(true);
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(PathBuf::from("dir/2024-05/My-Photo.jpg")),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_rules_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("dir/Scan_0001 (copy).pdf")));
+
+    let rules = RuleSet::from_toml(
+      "[[rules]]\nfind = \" (copy)\"\n\n[[rules]]\nfind = \"_\"\nreplace = \" \"\n",
+    )
+    .unwrap();
+    let task = ComputeNamesTask::new("\\0").with_rules(Some(rules));
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(PathBuf::from("dir/Scan 0001.pdf")),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_case_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("dir/UserProfile.tsx")));
+
+    let task = ComputeNamesTask::new("Components/\\0").with_case(Some(CaseStyle::Kebab));
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(PathBuf::from("dir/components/user-profile.tsx")),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_truncate_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    for c in ["a", "b"] {
+      let name = format!("dir/{}{}.webm", c, "x".repeat(300));
+      context.entries.push(RenameEntry::new(Path::new(&name)));
+    }
+
+    let task = ComputeNamesTask::new("??_\\0").with_truncate(Platform::Linux, TruncatePolicy::Cut);
+    assert!(task.execute(&mut context).is_ok());
+
+    let dest = context.entries[1].dest.clone().unwrap();
+    let name = dest.file_name().unwrap().to_string_lossy().to_string();
+    assert_eq!(255, name.len());
+    assert!(name.starts_with("02_bxx"));
+    assert!(name.ends_with("x.webm"));
+  }
+
+  #[test]
+  fn test_compute_truncate_2() {
+    let mut context = RenameContext::new(Path::new("."));
+    context.entries.push(RenameEntry::new(Path::new("a.txt")));
+
+    // no file name in the pattern
+    let pattern = format!("{}.txt", "あ".repeat(100));
+    let task = ComputeNamesTask::new(&pattern).with_truncate(Platform::Linux, TruncatePolicy::Hash);
+    assert!(task.execute(&mut context).is_ok());
+
+    let name = context.entries[0].dest.clone().unwrap();
+    let name = name.to_string_lossy();
+    assert!(name.len() <= 255);
+    assert!(name.ends_with(".txt"));
+    assert!(name.contains('~'));
+  }
+
+  #[test]
+  fn test_compute_truncate_rules_1() {
+    // 204 bytes, and 404 after "-" -> "---"
+    let file_name = format!("{}.txt", "a-".repeat(100));
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(&Path::new("dir").join(&file_name)));
+
+    let rules = RuleSet::from_toml("[[rules]]\nfind = \"-\"\nreplace = \"---\"\n").unwrap();
+    let task = ComputeNamesTask::new("\\0")
+      .with_rules(Some(rules))
+      .with_truncate(Platform::Linux, TruncatePolicy::Cut);
+    assert!(task.execute(&mut context).is_ok());
+    let dest = context.entries[0].dest.clone().unwrap();
+    let name = dest.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("a---a---"));
+    assert!(name.ends_with(".txt"));
+    assert!(Platform::Linux.length(name) <= 255);
+  }
+
+  #[test]
+  fn test_compute_truncate_sanitize_1() {
+    // no extension, cut right after the dot
+    let file_name = format!("{}. {}", "a".repeat(254), "b".repeat(20));
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(&Path::new("dir").join(&file_name)));
+
+    let task = ComputeNamesTask::new("\\0")
+      .with_sanitize(Platform::Windows, '_')
+      .with_truncate(Platform::Windows, TruncatePolicy::Cut);
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(
+      Some(Path::new("dir").join("a".repeat(254))),
+      context.entries[0].dest
+    );
+  }
+
+  #[test]
+  fn test_compute_truncate_case_1() {
+    // 244 UTF-16 code units, and 324 after "İ" -> "i\u{307}"
+    let file_name = format!("{}.txt", "Aİ ".repeat(80));
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(RenameEntry::new(&Path::new("dir").join(&file_name)));
+
+    let task = ComputeNamesTask::new("\\0")
+      .with_case(Some(CaseStyle::Title))
+      .with_truncate(Platform::Windows, TruncatePolicy::Cut);
+    assert!(task.execute(&mut context).is_ok());
+    let dest = context.entries[0].dest.clone().unwrap();
+    let name = dest.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("Ai\u{307} Ai\u{307} "));
+    assert!(name.ends_with(".txt"));
+    assert!(Platform::Windows.length(name) <= 255);
+  }
+
+  #[test]
+  fn test_compute_invalid_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context.entries.push(RenameEntry::new(Path::new("a.jpg")));
+
+    let task = ComputeNamesTask::new("\\Q");
+    let e = task.execute(&mut context).unwrap_err();
+    assert_eq!("compute", e.task);
+  }
+}
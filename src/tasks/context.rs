@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+
+use crate::event::{CancelToken, EventCallback, RenameEvent};
+use crate::tasks::rename::RenameJournal;
+
+///
+/// State shared by the tasks of the rename pipeline.
+///
+/// # Examples
+/// ```no_run
+/// use nae::filesystem::ItemType;
+/// use nae::natural_sort::NaturalSortOptions;
+/// use nae::task::Invoker;
+/// use nae::tasks::{
+///   apply::ApplyTask, compute::ComputeNamesTask, context::RenameContext,
+///   enumerate::EnumerateTask, filter::FilterTask, journal::JournalTask, sort::SortTask,
+///   validate::ValidateTask,
+/// };
+/// use std::path::Path;
+///
+/// let mut context = RenameContext::new(Path::new("./photos"));
+/// let mut invoker = Invoker::new(&mut context);
+/// invoker.add(EnumerateTask::new(false, ItemType::File));
+/// invoker.add(FilterTask::new(r"\.jpg$").unwrap());
+/// invoker.add(SortTask::new(NaturalSortOptions::default()));
+/// invoker.add(ComputeNamesTask::new("photo???.jpg"));
+/// invoker.add(ValidateTask::new());
+/// // custom tasks implementing `Task<RenameContext>` can be added anywhere
+/// invoker.add(ApplyTask::new(true));
+/// invoker.add(JournalTask::new(Path::new("journal.json")));
+/// invoker.execute_all().unwrap();
+/// ```
+///
+#[derive(Default)]
+pub struct RenameContext {
+  /// directory to rename files in
+  pub root: PathBuf,
+  pub entries: Vec<RenameEntry>,
+  /// renames actually done
+  pub journal: RenameJournal,
+  /// called for each `RenameEvent`
+  pub events: Option<EventCallback>,
+  /// stops renaming between files
+  pub cancel: CancelToken,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameEntry {
+  pub src: PathBuf,
+  /// new path, set by `ComputeNamesTask`
+  pub dest: Option<PathBuf>,
+}
+
+impl RenameContext {
+  pub fn new(root: &Path) -> Self {
+    Self {
+      root: root.to_path_buf(),
+      entries: vec![],
+      journal: RenameJournal::default(),
+      events: None,
+      cancel: CancelToken::new(),
+    }
+  }
+
+  pub fn emit(&mut self, event: RenameEvent) {
+    if let Some(callback) = self.events.as_mut() {
+      callback(&event);
+    }
+  }
+}
+
+impl RenameEntry {
+  pub fn new(src: &Path) -> Self {
+    Self {
+      src: src.to_path_buf(),
+      dest: None,
+    }
+  }
+
+  ///
+  /// File name of the source.
+  ///
+  pub fn file_name(&self) -> String {
+    self
+      .src
+      .file_name()
+      .unwrap_or_default()
+      .to_string_lossy()
+      .to_string()
+  }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::content_hash::{ContentHash, HashAlgorithm};
+use crate::event::{RenameEvent, code};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+use crate::tasks::validate::ConflictPolicy;
+
+///
+/// Find entries with the same contents, before computing the new names.
+/// Only files of the same size are hashed (BLAKE3), and directories are ignored.
+/// With `ConflictPolicy::Skip`, the first file of each group is kept and the others are skipped.
+/// If `RenameContext::cancel` is cancelled, fails before the next file.
+///
+pub struct DuplicateTask {
+  policy: ConflictPolicy,
+}
+
+impl DuplicateTask {
+  pub fn new(policy: ConflictPolicy) -> Self {
+    Self { policy }
+  }
+}
+
+impl Task<RenameContext> for DuplicateTask {
+  fn name(&self) -> &str {
+    "duplicate"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    // files of each size, in the order of the entries
+    let mut sizes: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, entry) in context.entries.iter().enumerate() {
+      if context.cancel.is_cancelled() {
+        return Err(TaskError::new(self.name(), "cancelled").with_code(code::CANCELLED));
+      }
+      match entry.src.metadata() {
+        Ok(m) if m.is_file() => sizes.entry(m.len()).or_default().push(i),
+        Ok(_) => {}
+        Err(e) => {
+          return Err(TaskError::new(
+            self.name(),
+            &format!("{}: {}", entry.src.display(), e),
+          ));
+        }
+      }
+    }
+
+    // index of the first file with the same contents, for each duplicate
+    let mut originals: HashMap<usize, usize> = HashMap::new();
+    for indices in sizes.values().filter(|v| v.len() > 1) {
+      let mut hashes: HashMap<String, usize> = HashMap::new();
+      for &i in indices {
+        if context.cancel.is_cancelled() {
+          return Err(TaskError::new(self.name(), "cancelled").with_code(code::CANCELLED));
+        }
+        let src = &context.entries[i].src;
+        let hash = ContentHash::file(src, HashAlgorithm::Blake3)
+          .map_err(|e| TaskError::new(self.name(), &format!("{}: {}", src.display(), e)))?;
+        match hashes.get(&hash) {
+          Some(&first) => {
+            originals.insert(i, first);
+          }
+          None => {
+            hashes.insert(hash, i);
+          }
+        }
+      }
+    }
+
+    if originals.is_empty() {
+      return Ok(());
+    }
+
+    let mut duplicates: Vec<(usize, usize)> = originals.into_iter().collect();
+    duplicates.sort();
+    let mut diagnostics = vec![];
+    for &(i, first) in &duplicates {
+      let (path, first) = (
+        context.entries[i].src.clone(),
+        context.entries[first].src.clone(),
+      );
+      let message = format!("same contents as {}", first.display());
+      match self.policy {
+        ConflictPolicy::Fail => {
+          diagnostics.push(format!("{}: {}", path.display(), message));
+          context.emit(RenameEvent::Conflict {
+            path,
+            dest: None,
+            code: code::DUPLICATE_CONTENT.to_string(),
+            message,
+          });
+        }
+        ConflictPolicy::Skip => context.emit(RenameEvent::Skipped {
+          path,
+          reason: message,
+        }),
+      }
+    }
+
+    if self.policy == ConflictPolicy::Fail {
+      let mut e = TaskError::new(
+        self.name(),
+        &format!("{} duplicate(s) found", diagnostics.len()),
+      )
+      .with_code(code::DUPLICATE_CONTENT);
+      e.diagnostics = diagnostics;
+      return Err(e);
+    }
+
+    let skipped: Vec<PathBuf> = duplicates
+      .iter()
+      .map(|(i, _)| context.entries[*i].src.clone())
+      .collect();
+    context.entries.retain(|e| !skipped.contains(&e.src));
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::DuplicateTask;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use crate::tasks::validate::ConflictPolicy;
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_duplicate_1() {
+    let dir = Path::new("test_duplicate_task_1");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.jpg"), "abc").unwrap();
+    fs::write(dir.join("b.jpg"), "abd").unwrap();
+    fs::write(dir.join("c.jpg"), "abc").unwrap();
+    fs::write(dir.join("d.jpg"), "abcd").unwrap();
+
+    let context = || {
+      let mut context = RenameContext::new(dir);
+      for name in ["sub", "a.jpg", "b.jpg", "c.jpg", "d.jpg"] {
+        context.entries.push(RenameEntry::new(&dir.join(name)));
+      }
+      context
+    };
+
+    let mut failed = context();
+    let e = DuplicateTask::new(ConflictPolicy::Fail).execute(&mut failed);
+    let mut skipped = context();
+    let r = DuplicateTask::new(ConflictPolicy::Skip).execute(&mut skipped);
+    fs::remove_dir_all(dir).unwrap();
+
+    let e = e.unwrap_err();
+    assert_eq!(
+      vec![format!(
+        "{}: same contents as {}",
+        dir.join("c.jpg").display(),
+        dir.join("a.jpg").display()
+      )],
+      e.diagnostics
+    );
+    assert_eq!(5, failed.entries.len());
+
+    assert!(r.is_ok());
+    assert_eq!(
+      vec!["sub", "a.jpg", "b.jpg", "d.jpg"],
+      skipped
+        .entries
+        .iter()
+        .map(|e| e.file_name())
+        .collect::<Vec<String>>()
+    );
+  }
+}
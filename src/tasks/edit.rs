@@ -0,0 +1,162 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::event::RenameEvent;
+use crate::filesystem::FileSystem;
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+
+///
+/// Compute the new names by editing the names of the entries in a text editor, one per line.
+/// Lines are paths relative to `RenameContext::root`, and must stay in the same order.
+///
+pub struct EditTask {
+  /// command line of the editor, the file to edit is appended.
+  /// Split at whitespace without quoting, so the path of the editor cannot contain spaces.
+  editor: String,
+}
+
+impl EditTask {
+  pub fn new(editor: &str) -> Self {
+    Self {
+      editor: editor.to_string(),
+    }
+  }
+
+  /// new file only readable by the user, not following links planted in a shared temporary directory
+  fn create() -> Result<(PathBuf, File), String> {
+    let dir = std::env::temp_dir();
+    let mut i = std::process::id();
+    loop {
+      let path = dir.join(format!("nae-edit-{:x}.txt", i));
+      let mut options = OpenOptions::new();
+      options.write(true).create_new(true);
+      #[cfg(unix)]
+      std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+      match options.open(&path) {
+        Ok(file) => return Ok((path, file)),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => i = i.wrapping_add(1),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+      }
+    }
+  }
+
+  /// run the editor on `path` and wait for it
+  fn edit(&self, path: &Path) -> Result<(), String> {
+    let mut words = self.editor.split_whitespace();
+    let program = words.next().ok_or("no editor")?;
+
+    match Command::new(program).args(words).arg(path).status() {
+      Ok(status) if status.success() => Ok(()),
+      Ok(status) => Err(format!("{}: {}", self.editor, status)),
+      Err(e) => Err(format!("{}: {}", self.editor, e)),
+    }
+  }
+}
+
+impl Task<RenameContext> for EditTask {
+  fn name(&self) -> &str {
+    "edit"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let root = context.root.clone();
+    let mut names = vec![];
+    for entry in &context.entries {
+      let name = entry.src.strip_prefix(&root).unwrap_or(&entry.src);
+      let name = name.to_string_lossy().to_string();
+      if name.contains(['\n', '\r']) {
+        return Err(TaskError::new(
+          self.name(),
+          &format!("{}: line breaks in the name", entry.src.display()),
+        ));
+      }
+      names.push(name);
+    }
+
+    let (path, mut file) = EditTask::create().map_err(|e| TaskError::new(self.name(), &e))?;
+    let text: String = names.iter().map(|n| format!("{}\n", n)).collect();
+    let written = file.write_all(text.as_bytes());
+    drop(file);
+    let edited = written
+      .map_err(|e| format!("{}: {}", path.display(), e))
+      .and_then(|_| self.edit(&path))
+      .and_then(|_| fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e)));
+    let _ = fs::remove_file(&path);
+    let edited = edited.map_err(|e| TaskError::new(self.name(), &e))?;
+
+    let lines: Vec<&str> = edited.lines().collect();
+    if lines.len() != names.len() {
+      return Err(TaskError::new(
+        self.name(),
+        &format!("{} lines edited, {} expected", lines.len(), names.len()),
+      ));
+    }
+    if let Some(i) = lines.iter().position(|l| l.is_empty()) {
+      return Err(TaskError::new(
+        self.name(),
+        &format!("line {}: empty name", i + 1),
+      ));
+    }
+
+    for (i, line) in lines.into_iter().enumerate() {
+      let entry = &mut context.entries[i];
+      let dest: PathBuf = match line == names[i] {
+        true => entry.src.clone(),
+        false => FileSystem::normalize_path(&root.join(line)),
+      };
+      entry.dest = Some(dest.clone());
+      let src = entry.src.clone();
+      context.emit(RenameEvent::Planned { src, dest });
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::EditTask;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use std::path::Path;
+
+  #[cfg(unix)]
+  #[test]
+  fn test_create_1() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (a, _) = EditTask::create().unwrap();
+    let (b, _) = EditTask::create().unwrap();
+    let mode = std::fs::metadata(&a).unwrap().permissions().mode();
+    std::fs::remove_file(&a).unwrap();
+    std::fs::remove_file(&b).unwrap();
+
+    assert_ne!(a, b);
+    assert_eq!(0o600, mode & 0o777);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_edit_1() {
+    let dir = Path::new("dir");
+    let mut context = RenameContext::new(dir);
+    for name in ["a.txt", "b.txt"] {
+      context.entries.push(RenameEntry::new(&dir.join(name)));
+    }
+
+    let task = EditTask::new("sed -i s/^a/sub\\/c/");
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!(Some(dir.join("sub/c.txt")), context.entries[0].dest);
+    assert_eq!(Some(dir.join("b.txt")), context.entries[1].dest);
+
+    // a line removed
+    let task = EditTask::new("sed -i 1d");
+    let e = task.execute(&mut context).unwrap_err();
+    assert_eq!("1 lines edited, 2 expected", e.message);
+
+    assert!(EditTask::new("false").execute(&mut context).is_err());
+  }
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use crate::config::DIR_CONFIG;
+use crate::event::{RenameEvent, code};
+use crate::filesystem::{FileSystem, ItemType};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::{RenameContext, RenameEntry};
+
+///
+/// Collect files and/or directories in `RenameContext::root`.
+/// Entries in a directory are collected before the directory itself.
+/// The config file of the root (`.nae.toml`) is not collected.
+/// If `RenameContext::cancel` is cancelled, fails once the directories are read.
+///
+pub struct EnumerateTask {
+  recursive: bool,
+  item_type: ItemType,
+}
+
+impl EnumerateTask {
+  pub fn new(recursive: bool, item_type: ItemType) -> Self {
+    Self {
+      recursive,
+      item_type,
+    }
+  }
+}
+
+impl Task<RenameContext> for EnumerateTask {
+  fn name(&self) -> &str {
+    "enumerate"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let root = context.root.clone();
+    let config = root.join(DIR_CONFIG);
+    let cancel = context.cancel.clone();
+    let mut paths = vec![];
+
+    FileSystem::enum_entries(&root, self.recursive, self.item_type, &mut |path: &Path| {
+      if path != config && !cancel.is_cancelled() {
+        paths.push(path.to_path_buf());
+      }
+    })
+    .map_err(|e| TaskError::new(self.name(), &format!("{}: {}", root.display(), e)))?;
+    if cancel.is_cancelled() {
+      return Err(TaskError::new(self.name(), "cancelled").with_code(code::CANCELLED));
+    }
+
+    for path in paths {
+      context.emit(RenameEvent::Scanned { path: path.clone() });
+      context.entries.push(RenameEntry::new(&path));
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::EnumerateTask;
+  use crate::filesystem::ItemType;
+  use crate::task::Task;
+  use crate::tasks::context::RenameContext;
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_enumerate_1() {
+    let dir = Path::new("test_enumerate_task_1");
+    assert!(fs::create_dir_all(dir.join("sub")).is_ok());
+    assert!(fs::write(dir.join("a.txt"), "\n").is_ok());
+    assert!(fs::write(dir.join(".nae.toml"), "\n").is_ok());
+    assert!(fs::write(dir.join("sub").join("b.txt"), "\n").is_ok());
+
+    let mut context = RenameContext::new(dir);
+    assert!(
+      EnumerateTask::new(true, ItemType::File)
+        .execute(&mut context)
+        .is_ok()
+    );
+
+    let mut names = context
+      .entries
+      .iter()
+      .map(|e| e.file_name())
+      .collect::<Vec<String>>();
+    names.sort();
+    assert_eq!(vec!["a.txt", "b.txt"], names);
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_enumerate_invalid_1() {
+    let mut context = RenameContext::new(Path::new("README.md"));
+    let e = EnumerateTask::new(false, ItemType::File)
+      .execute(&mut context)
+      .unwrap_err();
+    assert_eq!("enumerate", e.task);
+  }
+
+  #[test]
+  fn test_enumerate_directories_1() {
+    let dir = Path::new("test_enumerate_task_2");
+    assert!(fs::create_dir_all(dir.join("sub").join("subsub")).is_ok());
+    assert!(fs::write(dir.join("a.txt"), "\n").is_ok());
+
+    let mut context = RenameContext::new(dir);
+    let task = EnumerateTask::new(true, ItemType::Directory);
+    assert!(task.execute(&mut context).is_ok());
+
+    let paths: Vec<_> = context.entries.iter().map(|e| e.src.clone()).collect();
+    assert_eq!(vec![dir.join("sub").join("subsub"), dir.join("sub")], paths);
+
+    assert!(fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_enumerate_cancel_1() {
+    let mut context = RenameContext::new(Path::new("src"));
+    context.cancel.cancel();
+    let e = EnumerateTask::new(true, ItemType::File)
+      .execute(&mut context)
+      .unwrap_err();
+    assert_eq!(Some("cancelled".to_string()), e.code);
+    assert!(context.entries.is_empty());
+  }
+}
@@ -0,0 +1,64 @@
+use regex::Regex;
+
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+
+///
+/// Keep only the entries whose file name matches the regex.
+///
+pub struct FilterTask {
+  pattern: Regex,
+}
+
+impl FilterTask {
+  pub fn new(pattern: &str) -> Result<Self, TaskError> {
+    match Regex::new(pattern) {
+      Ok(pattern) => Ok(Self { pattern }),
+      Err(e) => Err(TaskError::new("filter", &e.to_string())),
+    }
+  }
+}
+
+impl Task<RenameContext> for FilterTask {
+  fn name(&self) -> &str {
+    "filter"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    context
+      .entries
+      .retain(|e| self.pattern.is_match(&e.file_name()));
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::FilterTask;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use std::path::Path;
+
+  #[test]
+  fn test_filter_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    for name in ["a.jpg", "b.png", "jpg.txt", "c.JPG"] {
+      context.entries.push(RenameEntry::new(Path::new(name)));
+    }
+
+    let task = FilterTask::new(r"(?i)\.jpg$").unwrap();
+    assert!(task.execute(&mut context).is_ok());
+
+    let names = context
+      .entries
+      .iter()
+      .map(|e| e.file_name())
+      .collect::<Vec<String>>();
+    assert_eq!(vec!["a.jpg", "c.JPG"], names);
+  }
+
+  #[test]
+  fn test_filter_invalid_1() {
+    assert!(FilterTask::new("(").is_err());
+  }
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use crate::event::RenameEvent;
+use crate::filesystem::FileSystem;
+use crate::mapping::Mapping;
+use crate::task::{Task, TaskError};
+use crate::tasks::context::{RenameContext, RenameEntry};
+
+///
+/// Collect entries and their new names from a CSV/TSV mapping file, instead of a pattern.
+/// Rows whose file is not found are reported as skipped.
+///
+pub struct ImportTask {
+  path: PathBuf,
+}
+
+impl ImportTask {
+  pub fn new(path: &Path) -> Self {
+    Self {
+      path: path.to_path_buf(),
+    }
+  }
+}
+
+impl Task<RenameContext> for ImportTask {
+  fn name(&self) -> &str {
+    "import"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let rows = Mapping::read(&self.path).map_err(|e| TaskError::new(self.name(), &e))?;
+    if !context.root.is_dir() {
+      return Err(TaskError::new(
+        self.name(),
+        &format!("{}: not a directory", context.root.display()),
+      ));
+    }
+
+    let root = context.root.clone();
+    let (plan, unmatched) = Mapping::to_plan(&root, &rows);
+
+    for u in unmatched {
+      context.emit(RenameEvent::Skipped {
+        path: root.join(&u.src),
+        reason: format!("line {}: {}", u.line, u.reason),
+      });
+    }
+
+    for element in plan.get_plans() {
+      let src = root.join(element.get_source());
+      let dest = FileSystem::normalize_path(&root.join(element.get_destination()));
+      context.emit(RenameEvent::Scanned { path: src.clone() });
+      context.emit(RenameEvent::Planned {
+        src: src.clone(),
+        dest: dest.clone(),
+      });
+
+      let mut entry = RenameEntry::new(&src);
+      entry.dest = Some(dest);
+      context.entries.push(entry);
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ImportTask;
+  use crate::event::RenameEvent;
+  use crate::filesystem::ItemType;
+  use crate::mapping::Mapping;
+  use crate::plan::Plan;
+  use crate::task::Task;
+  use crate::tasks::compute::ComputeNamesTask;
+  use crate::tasks::context::RenameContext;
+  use crate::tasks::enumerate::EnumerateTask;
+  use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+  };
+
+  #[test]
+  fn test_import_1() {
+    let dir = Path::new("test_import_task_1");
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("a.jpg"), "a").unwrap();
+    let csv = dir.join("mapping.csv");
+    fs::write(&csv, "old,new\na.jpg,sku/1.jpg\nb.jpg,2.jpg\n").unwrap();
+
+    let mut context = RenameContext::new(dir);
+    let skipped = Rc::new(RefCell::new(vec![]));
+    let s = skipped.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      if let RenameEvent::Skipped { reason, .. } = event {
+        s.borrow_mut().push(reason.clone());
+      }
+    }));
+
+    let r = ImportTask::new(&csv).execute(&mut context);
+    fs::remove_dir_all(dir).unwrap();
+
+    assert!(r.is_ok());
+    assert_eq!(1, context.entries.len());
+    assert_eq!(dir.join("a.jpg"), context.entries[0].src);
+    assert_eq!(Some(dir.join("sku/1.jpg")), context.entries[0].dest);
+    assert_eq!(vec!["line 3: not found"], *skipped.borrow());
+  }
+
+  #[test]
+  fn test_import_nested_1() {
+    let dir = Path::new("test_import_task_2");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.jpg"), "root").unwrap();
+    fs::write(dir.join("sub").join("a.jpg"), "sub").unwrap();
+    fs::write(dir.join("sub").join("b.jpg"), "sub").unwrap();
+
+    // a recursive plan exported and imported again
+    let mut context = RenameContext::new(dir);
+    let r = EnumerateTask::new(true, ItemType::File)
+      .execute(&mut context)
+      .and_then(|_| ComputeNamesTask::new("x_\\0").execute(&mut context));
+    let csv = dir.join("..").join("test_import_task_2.csv");
+    let plan = Plan::from_entries(dir, &context.entries).unwrap();
+    Mapping::write_plan(&plan, fs::File::create(&csv).unwrap(), b',').unwrap();
+
+    let mut imported = RenameContext::new(dir);
+    let i = ImportTask::new(&csv).execute(&mut imported);
+    let text = fs::read_to_string(&csv).unwrap();
+    fs::remove_file(&csv).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+
+    assert!(r.is_ok());
+    assert!(i.is_ok());
+    assert!(text.contains("\nsub/a.jpg,sub/x_a.jpg,"));
+    let pairs = |c: &RenameContext| {
+      let mut pairs: Vec<(PathBuf, Option<PathBuf>)> = c
+        .entries
+        .iter()
+        .map(|e| (e.src.clone(), e.dest.clone()))
+        .collect();
+      pairs.sort();
+      pairs
+    };
+    assert_eq!(3, imported.entries.len());
+    assert_eq!(pairs(&context), pairs(&imported));
+  }
+}
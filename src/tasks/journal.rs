@@ -0,0 +1,78 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+
+///
+/// Write the journal of the renames to a JSON file.
+///
+pub struct JournalTask {
+  path: PathBuf,
+}
+
+impl JournalTask {
+  pub fn new(path: &Path) -> Self {
+    Self {
+      path: path.to_path_buf(),
+    }
+  }
+}
+
+impl Task<RenameContext> for JournalTask {
+  fn name(&self) -> &str {
+    "journal"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let json = serde_json::to_string_pretty(&context.journal)
+      .map_err(|e| TaskError::new(self.name(), &e.to_string()))?;
+
+    fs::write(&self.path, json)
+      .map_err(|e| TaskError::new(self.name(), &format!("{}: {}", self.path.display(), e)))
+  }
+
+  fn undo(&self, _context: &mut RenameContext) -> Result<(), TaskError> {
+    match fs::remove_file(&self.path) {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(TaskError::new(
+        self.name(),
+        &format!("{}: {}", self.path.display(), e),
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::JournalTask;
+  use crate::task::Task;
+  use crate::tasks::context::RenameContext;
+  use crate::tasks::rename::{JournalEntry, RenameJournal};
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_journal_1() {
+    let path = Path::new("test_journal_task_1.json");
+    let mut context = RenameContext::new(Path::new("."));
+    context.journal.renamed.push(JournalEntry {
+      src: Path::new("a.txt").to_path_buf(),
+      dest: Path::new("b.txt").to_path_buf(),
+      created_dirs: vec![],
+      copied: false,
+      swapped: false,
+    });
+
+    let task = JournalTask::new(path);
+    assert!(task.execute(&mut context).is_ok());
+
+    let json = fs::read_to_string(path).unwrap();
+    let journal: RenameJournal = serde_json::from_str(&json).unwrap();
+    assert_eq!(context.journal, journal);
+
+    assert!(task.undo(&mut context).is_ok());
+    assert!(!path.exists());
+  }
+}
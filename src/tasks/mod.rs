@@ -0,0 +1,14 @@
+pub mod apply;
+pub mod compute;
+pub mod context;
+pub mod duplicate;
+pub mod edit;
+pub mod enumerate;
+pub mod filter;
+pub mod import;
+pub mod journal;
+pub mod rename;
+pub mod sort;
+pub mod undo;
+pub mod validate;
+pub mod version;
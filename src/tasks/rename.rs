@@ -0,0 +1,308 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::code;
+use crate::filesystem::{FileSystem, MoveMethod, Verify};
+use crate::task::{Task, TaskError};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenameJournal {
+  /// renamed files, in order
+  pub renamed: Vec<JournalEntry>,
+  /// stopped by `CancelToken` before all files were renamed
+  #[serde(default)]
+  pub cancelled: bool,
+  /// directories removed because they became empty, in order
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub removed_dirs: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+  pub src: PathBuf,
+  pub dest: PathBuf,
+  /// directories created for `dest`, outermost first
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub created_dirs: Vec<PathBuf>,
+  /// moved to another file system by copying, so undo copies back
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub copied: bool,
+  /// `src` and `dest` were exchanged, so undo exchanges them again
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub swapped: bool,
+}
+
+impl JournalEntry {
+  ///
+  /// Rename back to `src`, and remove the directories created for `dest` if they are empty.
+  ///
+  pub fn undo(&self) -> std::io::Result<()> {
+    if self.swapped {
+      return FileSystem::exchange(&self.src, &self.dest);
+    }
+
+    FileSystem::move_path(&self.dest, &self.src, Verify::Size)?;
+
+    for dir in self.created_dirs.iter().rev() {
+      // still used by other files
+      if std::fs::remove_dir(dir).is_err() {
+        break;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+///
+/// Rename a file or directory.
+/// `dest` may be in another directory, the missing directories are created.
+/// If `dest` is on another file system, the file is copied and the source removed.
+///
+pub struct RenameTask {
+  src: PathBuf,
+  dest: PathBuf,
+  verify: Verify,
+}
+
+impl RenameTask {
+  pub fn new(src: &Path, dest: &Path) -> Self {
+    Self {
+      src: src.to_path_buf(),
+      dest: dest.to_path_buf(),
+      verify: Verify::default(),
+    }
+  }
+
+  ///
+  /// Set the check of the data copied to another file system.
+  ///
+  pub fn with_verify(mut self, verify: Verify) -> Self {
+    self.verify = verify;
+    self
+  }
+}
+
+impl Task<RenameJournal> for RenameTask {
+  fn name(&self) -> &str {
+    "rename"
+  }
+
+  fn execute(&self, journal: &mut RenameJournal) -> Result<(), TaskError> {
+    if self.src == self.dest {
+      // NOP
+      return Ok(());
+    }
+
+    let created_dirs = FileSystem::create_parent_dirs(&self.dest).map_err(|e| {
+      TaskError::new(self.name(), &format!("{}: {}", self.dest.display(), e))
+        .with_code(code::from_io(e.kind()))
+    })?;
+
+    let mut entry = JournalEntry {
+      src: self.src.to_path_buf(),
+      dest: self.dest.to_path_buf(),
+      created_dirs,
+      copied: false,
+      swapped: false,
+    };
+
+    // never overwrite, an overwritten file cannot be restored by undo()
+    match FileSystem::move_path(&self.src, &self.dest, self.verify) {
+      Ok(method) => {
+        entry.copied = method == MoveMethod::Copied;
+        journal.renamed.push(entry);
+        Ok(())
+      }
+      Err(e) => {
+        for dir in entry.created_dirs.iter().rev() {
+          let _ = std::fs::remove_dir(dir);
+        }
+        let message = match e.kind() {
+          ErrorKind::AlreadyExists => format!("{} already exists", self.dest.display()),
+          _ => format!("{}: {}", self.src.display(), e),
+        };
+        Err(TaskError::new(self.name(), &message).with_code(code::from_io(e.kind())))
+      }
+    }
+  }
+
+  fn undo(&self, journal: &mut RenameJournal) -> Result<(), TaskError> {
+    if self.src == self.dest {
+      // NOP
+      return Ok(());
+    }
+
+    let entry = match journal.renamed.last() {
+      Some(entry) if entry.dest == self.dest => entry,
+      _ => {
+        return Err(TaskError::new(
+          self.name(),
+          &format!("{}: not in the journal", self.dest.display()),
+        ));
+      }
+    };
+
+    match entry.undo() {
+      Ok(()) => {
+        journal.renamed.pop();
+        Ok(())
+      }
+      Err(e) => Err(TaskError::new(
+        self.name(),
+        &format!("{}: {}", self.dest.display(), e),
+      )),
+    }
+  }
+}
+
+///
+/// Exchange the names of two files or directories, e.g. "a" -> "b" and "b" -> "a".
+///
+pub struct SwapTask {
+  a: PathBuf,
+  b: PathBuf,
+}
+
+impl SwapTask {
+  pub fn new(a: &Path, b: &Path) -> Self {
+    Self {
+      a: a.to_path_buf(),
+      b: b.to_path_buf(),
+    }
+  }
+}
+
+impl Task<RenameJournal> for SwapTask {
+  fn name(&self) -> &str {
+    "swap"
+  }
+
+  fn execute(&self, journal: &mut RenameJournal) -> Result<(), TaskError> {
+    match FileSystem::exchange(&self.a, &self.b) {
+      Ok(()) => {
+        journal.renamed.push(JournalEntry {
+          src: self.a.to_path_buf(),
+          dest: self.b.to_path_buf(),
+          created_dirs: vec![],
+          copied: false,
+          swapped: true,
+        });
+        Ok(())
+      }
+      Err(e) => Err(
+        TaskError::new(
+          self.name(),
+          &format!("{} <-> {}: {}", self.a.display(), self.b.display(), e),
+        )
+        .with_code(code::from_io(e.kind())),
+      ),
+    }
+  }
+
+  fn undo(&self, journal: &mut RenameJournal) -> Result<(), TaskError> {
+    match FileSystem::exchange(&self.a, &self.b) {
+      Ok(()) => {
+        journal.renamed.pop();
+        Ok(())
+      }
+      Err(e) => Err(TaskError::new(
+        self.name(),
+        &format!("{} <-> {}: {}", self.a.display(), self.b.display(), e),
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{RenameJournal, RenameTask, SwapTask};
+  use crate::task::Task;
+  use std::{fs::File, path::Path};
+
+  #[test]
+  fn test_execute_and_undo_1() {
+    let src = Path::new("test_rename_task_1.txt");
+    let dest = Path::new("test_rename_task_1.bak");
+    let mut journal = RenameJournal::default();
+
+    assert!(File::create(src).is_ok());
+
+    let task = RenameTask::new(src, dest);
+    assert!(task.execute(&mut journal).is_ok());
+    assert!(!src.exists());
+    assert!(dest.exists());
+    assert_eq!(1, journal.renamed.len());
+
+    assert!(task.undo(&mut journal).is_ok());
+    assert!(src.exists());
+    assert!(!dest.exists());
+    assert!(journal.renamed.is_empty());
+
+    assert!(std::fs::remove_file(src).is_ok());
+  }
+
+  #[test]
+  fn test_execute_exists_1() {
+    let src = Path::new("test_rename_task_2.txt");
+    let dest = Path::new("test_rename_task_2.bak");
+    let mut journal = RenameJournal::default();
+
+    assert!(File::create(src).is_ok());
+    assert!(File::create(dest).is_ok());
+
+    let task = RenameTask::new(src, dest);
+    assert!(task.execute(&mut journal).is_err());
+    assert!(src.exists());
+    assert!(journal.renamed.is_empty());
+
+    assert!(std::fs::remove_file(src).is_ok());
+    assert!(std::fs::remove_file(dest).is_ok());
+  }
+
+  #[test]
+  fn test_execute_create_dirs_1() {
+    let dir = Path::new("test_rename_task_3");
+    let src = dir.join("a.jpg");
+    let dest = dir.join("2024").join("01").join("a.jpg");
+    let mut journal = RenameJournal::default();
+
+    assert!(std::fs::create_dir_all(dir).is_ok());
+    assert!(File::create(&src).is_ok());
+
+    let task = RenameTask::new(&src, &dest);
+    assert!(task.execute(&mut journal).is_ok());
+    assert!(dest.exists());
+    assert_eq!(2, journal.renamed[0].created_dirs.len());
+
+    assert!(task.undo(&mut journal).is_ok());
+    assert!(src.exists());
+    assert!(!dir.join("2024").exists());
+
+    assert!(std::fs::remove_dir_all(dir).is_ok());
+  }
+
+  #[test]
+  fn test_swap_1() {
+    let a = Path::new("test_rename_task_4.a");
+    let b = Path::new("test_rename_task_4.b");
+    let mut journal = RenameJournal::default();
+
+    assert!(std::fs::write(a, "a").is_ok());
+    assert!(std::fs::write(b, "b").is_ok());
+
+    let task = SwapTask::new(a, b);
+    assert!(task.execute(&mut journal).is_ok());
+    assert_eq!("b", std::fs::read_to_string(a).unwrap());
+    assert!(journal.renamed[0].swapped);
+
+    // undo from the journal, as `ApplyTask::undo()`
+    assert!(journal.renamed[0].undo().is_ok());
+    assert_eq!("a", std::fs::read_to_string(a).unwrap());
+
+    assert!(std::fs::remove_file(a).is_ok());
+    assert!(std::fs::remove_file(b).is_ok());
+  }
+}
@@ -0,0 +1,81 @@
+use crate::natural_sort::{NaturalKey, NaturalSortOptions};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+
+///
+/// Sort the entries by path in natural order.
+///
+pub struct SortTask {
+  options: NaturalSortOptions,
+  /// descending order
+  reverse: bool,
+}
+
+impl SortTask {
+  pub fn new(options: NaturalSortOptions) -> Self {
+    Self {
+      options,
+      reverse: false,
+    }
+  }
+
+  pub fn with_reverse(mut self, enabled: bool) -> Self {
+    self.reverse = enabled;
+    self
+  }
+}
+
+impl Task<RenameContext> for SortTask {
+  fn name(&self) -> &str {
+    "sort"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    context
+      .entries
+      .sort_by_cached_key(|e| NaturalKey::new(&e.src.to_string_lossy(), &self.options));
+    if self.reverse {
+      context.entries.reverse();
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::SortTask;
+  use crate::natural_sort::NaturalSortOptions;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use std::path::Path;
+
+  #[test]
+  fn test_sort_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    for name in ["img10.jpg", "img9.jpg", "img1.jpg"] {
+      context.entries.push(RenameEntry::new(Path::new(name)));
+    }
+
+    let task = SortTask::new(NaturalSortOptions::default());
+    assert!(task.execute(&mut context).is_ok());
+
+    let names = context
+      .entries
+      .iter()
+      .map(|e| e.file_name())
+      .collect::<Vec<String>>();
+    assert_eq!(vec!["img1.jpg", "img9.jpg", "img10.jpg"], names);
+  }
+
+  #[test]
+  fn test_sort_reverse_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    for name in ["img10.jpg", "img9.jpg", "img1.jpg"] {
+      context.entries.push(RenameEntry::new(Path::new(name)));
+    }
+
+    let task = SortTask::new(NaturalSortOptions::default()).with_reverse(true);
+    assert!(task.execute(&mut context).is_ok());
+    assert_eq!("img10.jpg", context.entries[0].file_name());
+  }
+}
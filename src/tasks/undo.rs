@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::event::{RenameEvent, code};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::{RenameContext, RenameEntry};
+use crate::tasks::rename::RenameJournal;
+
+///
+/// Rename the files of a journal written by `JournalTask` back to their old names, latest first.
+/// Entries are the renames back, e.g. "b.txt" -> "a.txt" for "a.txt" -> "b.txt".
+///
+pub struct UndoTask {
+  path: PathBuf,
+  dry_run: bool,
+}
+
+impl UndoTask {
+  pub fn new(path: &Path, dry_run: bool) -> Self {
+    Self {
+      path: path.to_path_buf(),
+      dry_run,
+    }
+  }
+}
+
+impl Task<RenameContext> for UndoTask {
+  fn name(&self) -> &str {
+    "undo"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    let journal = fs::read_to_string(&self.path)
+      .map_err(|e| e.to_string())
+      .and_then(|s| serde_json::from_str::<RenameJournal>(&s).map_err(|e| e.to_string()))
+      .map_err(|e| TaskError::new(self.name(), &format!("{}: {}", self.path.display(), e)))?;
+
+    for entry in journal.renamed.iter().rev() {
+      context.emit(RenameEvent::Planned {
+        src: entry.dest.clone(),
+        dest: entry.src.clone(),
+      });
+      let mut e = RenameEntry::new(&entry.dest);
+      e.dest = Some(entry.src.clone());
+      context.entries.push(e);
+    }
+
+    if self.dry_run {
+      return Ok(());
+    }
+
+    for dir in journal.removed_dirs.iter().rev() {
+      fs::create_dir_all(dir)
+        .map_err(|e| TaskError::new(self.name(), &format!("{}: {}", dir.display(), e)))?;
+    }
+
+    for entry in journal.renamed.iter().rev() {
+      let start = Instant::now();
+      if let Err(e) = entry.undo() {
+        let message = format!("{}: {}", entry.dest.display(), e);
+        context.emit(RenameEvent::Failed {
+          path: entry.dest.clone(),
+          dest: Some(entry.src.clone()),
+          code: code::from_io(e.kind()).to_string(),
+          message: e.to_string(),
+          elapsed: start.elapsed(),
+        });
+        return Err(TaskError::new(self.name(), &message).with_code(code::from_io(e.kind())));
+      }
+      context.emit(RenameEvent::Renamed {
+        src: entry.dest.clone(),
+        dest: entry.src.clone(),
+        elapsed: start.elapsed(),
+      });
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::UndoTask;
+  use crate::task::Task;
+  use crate::tasks::context::RenameContext;
+  use crate::tasks::rename::{JournalEntry, RenameJournal};
+  use std::{fs, path::Path};
+
+  #[test]
+  fn test_undo_1() {
+    let dir = Path::new("test_undo_task_1");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub").join("b.txt"), "a").unwrap();
+    fs::write(dir.join("c.txt"), "c").unwrap();
+
+    let journal = RenameJournal {
+      renamed: vec![
+        JournalEntry {
+          src: dir.join("a.txt"),
+          dest: dir.join("sub").join("b.txt"),
+          created_dirs: vec![dir.join("sub")],
+          copied: false,
+          swapped: false,
+        },
+        JournalEntry {
+          src: dir.join("b.txt"),
+          dest: dir.join("c.txt"),
+          created_dirs: vec![],
+          copied: false,
+          swapped: false,
+        },
+      ],
+      cancelled: false,
+      removed_dirs: vec![],
+    };
+    let path = dir.join("journal.json");
+    fs::write(&path, serde_json::to_string(&journal).unwrap()).unwrap();
+
+    let mut context = RenameContext::new(dir);
+    let dry_run = UndoTask::new(&path, true).execute(&mut context);
+    let unchanged = dir.join("c.txt").exists();
+    let r = UndoTask::new(&path, false).execute(&mut RenameContext::new(dir));
+    let names = (
+      fs::read_to_string(dir.join("a.txt")).ok(),
+      dir.join("b.txt").exists(),
+      dir.join("sub").exists(),
+    );
+    fs::remove_dir_all(dir).unwrap();
+
+    assert!(dry_run.is_ok());
+    assert!(unchanged);
+    assert_eq!(2, context.entries.len());
+    assert_eq!(dir.join("c.txt"), context.entries[0].src);
+    assert_eq!(Some(dir.join("b.txt")), context.entries[0].dest);
+
+    assert!(r.is_ok());
+    assert_eq!((Some("a".to_string()), true, false), names);
+  }
+}
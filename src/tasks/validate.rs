@@ -0,0 +1,552 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::event::{RenameEvent, code};
+use crate::filesystem::FileSystem;
+use crate::normalize::{NormalizationForm, Normalizer};
+use crate::task::{Task, TaskError};
+use crate::tasks::context::RenameContext;
+use crate::validity::{Platform, Validity, Violation};
+use clap::ValueEnum;
+
+///
+/// What to do with entries whose new names are rejected.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ConflictPolicy {
+  /// rename nothing
+  #[default]
+  Fail,
+  /// leave the conflicting files as they are and rename the others
+  Skip,
+}
+
+///
+/// Check the new names before renaming.
+/// Fails if new names are missing, duplicated (also after NFC normalization), already used by other files,
+/// outside the root, or not allowed on the target platform.
+///
+#[derive(Default)]
+pub struct ValidateTask {
+  case_insensitive: bool,
+  /// check names against the rules of the platform, none to skip
+  platform: Option<Platform>,
+  conflict_policy: ConflictPolicy,
+}
+
+impl ValidateTask {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  ///
+  /// Compare names ignoring case, for case-insensitive file systems (e.g. exFAT).
+  ///
+  pub fn with_case_insensitive(mut self, enabled: bool) -> Self {
+    self.case_insensitive = enabled;
+    self
+  }
+
+  ///
+  /// Reject names not allowed on the platform, e.g. files synced to Windows.
+  ///
+  pub fn with_platform(mut self, platform: Option<Platform>) -> Self {
+    self.platform = platform;
+    self
+  }
+
+  pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+    self.conflict_policy = policy;
+    self
+  }
+
+  /// names created by renaming `src` to `dest`, e.g. "2024" and "a.jpg" for "dir/a.jpg" -> "dir/2024/a.jpg"
+  fn new_names(src: &Path, dest: &Path) -> Vec<String> {
+    let dir = src.parent().unwrap_or(Path::new(""));
+    match dest.strip_prefix(dir) {
+      Ok(rest) => rest
+        .components()
+        .filter_map(|c| match c {
+          Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+          _ => None,
+        })
+        .collect(),
+      Err(_) => dest
+        .file_name()
+        .map(|n| vec![n.to_string_lossy().to_string()])
+        .unwrap_or_default(),
+    }
+  }
+
+  fn key(&self, path: &Path) -> String {
+    if self.case_insensitive {
+      FileSystem::case_fold(path)
+    } else {
+      path.to_string_lossy().to_string()
+    }
+  }
+
+  /// `dest` is used by another file, e.g. "PHOTO.JPG" for "photo.jpg" when ignoring case
+  fn exists(
+    &self,
+    dest: &Path,
+    cache: &mut HashMap<PathBuf, HashMap<String, PathBuf>>,
+  ) -> Option<PathBuf> {
+    if dest.symlink_metadata().is_ok() {
+      return Some(dest.to_path_buf());
+    }
+    if !self.case_insensitive {
+      return None;
+    }
+
+    let dir = dest.parent().unwrap_or(Path::new("")).to_path_buf();
+    let names = cache.entry(dir.clone()).or_insert_with(|| {
+      let read_dir = if dir.as_os_str().is_empty() {
+        Path::new(".").read_dir()
+      } else {
+        dir.read_dir()
+      };
+      read_dir
+        .map(|entries| {
+          entries
+            .filter_map(|e| e.ok())
+            .map(|e| (self.key(&dir.join(e.file_name())), dir.join(e.file_name())))
+            .collect()
+        })
+        .unwrap_or_default()
+    });
+
+    names.get(&self.key(dest)).cloned()
+  }
+}
+
+impl ValidateTask {
+  /// diagnostics and conflict events of the entries
+  fn check(&self, context: &RenameContext) -> (Vec<String>, Vec<RenameEvent>) {
+    let mut diagnostics = vec![];
+    let mut conflicts = vec![];
+    let sources: HashSet<String> = context.entries.iter().map(|e| self.key(&e.src)).collect();
+    let mut order: Vec<(String, &PathBuf)> = vec![];
+    let mut destinations: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+    let mut cache = HashMap::new();
+
+    for entry in &context.entries {
+      let dest = match entry.dest.as_ref() {
+        Some(dest) => dest,
+        None => {
+          diagnostics.push(format!("{}: new name not computed", entry.src.display()));
+          conflicts.push(RenameEvent::Conflict {
+            path: entry.src.clone(),
+            dest: None,
+            code: code::NOT_COMPUTED.to_string(),
+            message: "new name not computed".to_string(),
+          });
+          continue;
+        }
+      };
+
+      if !FileSystem::is_within(dest, &context.root) {
+        diagnostics.push(format!(
+          "{} -> {}: outside {}",
+          entry.src.display(),
+          dest.display(),
+          context.root.display()
+        ));
+        conflicts.push(RenameEvent::Conflict {
+          path: entry.src.clone(),
+          dest: Some(dest.clone()),
+          code: code::OUTSIDE_ROOT.to_string(),
+          message: format!("outside {}", context.root.display()),
+        });
+      }
+
+      if let Some(platform) = self.platform {
+        let violations: Vec<Violation> = ValidateTask::new_names(&entry.src, dest)
+          .iter()
+          .flat_map(|name| Validity::check(name, platform))
+          .collect();
+        if !violations.is_empty() {
+          let code = match violations.as_slice() {
+            [Violation::TooLong { .. }] => code::NAME_TOO_LONG,
+            _ => code::INVALID_NAME,
+          };
+          let message = violations
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+          diagnostics.push(format!(
+            "{} -> {}: {}",
+            entry.src.display(),
+            dest.display(),
+            message
+          ));
+          conflicts.push(RenameEvent::Conflict {
+            path: entry.src.clone(),
+            dest: Some(dest.clone()),
+            code: code.to_string(),
+            message,
+          });
+        }
+      }
+
+      let existing = match dest != &entry.src {
+        true => self.exists(dest, &mut cache),
+        false => None,
+      };
+      if let Some(existing) = existing
+        && !sources.contains(&self.key(&existing))
+        // a case-only change on a case-insensitive file system
+        && !FileSystem::is_same_file(&existing, &entry.src)
+      {
+        diagnostics.push(format!(
+          "{} -> {}: already exists",
+          entry.src.display(),
+          dest.display()
+        ));
+        conflicts.push(RenameEvent::Conflict {
+          path: entry.src.clone(),
+          dest: Some(dest.clone()),
+          code: code::ALREADY_EXISTS.to_string(),
+          message: "already exists".to_string(),
+        });
+      }
+
+      let key = self.key(dest);
+      destinations
+        .entry(key.clone())
+        .or_insert_with(|| {
+          order.push((key, dest));
+          vec![]
+        })
+        .push(&entry.src);
+    }
+
+    for (key, dest) in &order {
+      let srcs = &destinations[key];
+      if srcs.len() > 1 {
+        diagnostics.push(format!(
+          "{} -> {}: duplicated",
+          srcs
+            .iter()
+            .map(|s| s.display().to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+          dest.display()
+        ));
+        for src in srcs {
+          conflicts.push(RenameEvent::Conflict {
+            path: src.to_path_buf(),
+            dest: Some(dest.to_path_buf()),
+            code: code::DUPLICATED.to_string(),
+            message: "duplicated".to_string(),
+          });
+        }
+      }
+    }
+
+    // e.g. "が" composed and decomposed, one name on file systems normalizing names
+    let paths: Vec<String> = order
+      .iter()
+      .map(|(_, dest)| dest.to_string_lossy().to_string())
+      .collect();
+    let names: Vec<&str> = paths.iter().map(|p| p.as_str()).collect();
+    for collision in Normalizer::find_collisions(&names, NormalizationForm::Nfc) {
+      diagnostics.push(format!(
+        "{}: same name after Unicode normalization",
+        collision.names.join(", ")
+      ));
+      for (i, (key, dest)) in order.iter().enumerate() {
+        if !collision.names.contains(&paths[i]) {
+          continue;
+        }
+        for src in &destinations[key] {
+          conflicts.push(RenameEvent::Conflict {
+            path: src.to_path_buf(),
+            dest: Some(dest.to_path_buf()),
+            code: code::NORMALIZATION_COLLISION.to_string(),
+            message: "same name after Unicode normalization".to_string(),
+          });
+        }
+      }
+    }
+
+    (diagnostics, conflicts)
+  }
+}
+
+impl Task<RenameContext> for ValidateTask {
+  fn name(&self) -> &str {
+    "validate"
+  }
+
+  fn execute(&self, context: &mut RenameContext) -> Result<(), TaskError> {
+    if self.conflict_policy == ConflictPolicy::Skip {
+      // skipping an entry may leave its source in the way of another one, check again
+      loop {
+        let (_, conflicts) = self.check(context);
+        if conflicts.is_empty() {
+          return Ok(());
+        }
+
+        let mut reasons: HashMap<PathBuf, String> = HashMap::new();
+        for conflict in conflicts {
+          if let RenameEvent::Conflict { path, message, .. } = &conflict {
+            reasons.entry(path.clone()).or_insert(message.clone());
+          }
+          context.emit(conflict);
+        }
+        let (skipped, entries) = std::mem::take(&mut context.entries)
+          .into_iter()
+          .partition(|e| reasons.contains_key(&e.src));
+        context.entries = entries;
+        for entry in skipped {
+          context.emit(RenameEvent::Skipped {
+            reason: reasons[&entry.src].clone(),
+            path: entry.src,
+          });
+        }
+      }
+    }
+
+    let (diagnostics, conflicts) = self.check(context);
+    for conflict in conflicts {
+      context.emit(conflict);
+    }
+
+    if diagnostics.is_empty() {
+      return Ok(());
+    }
+
+    let mut e = TaskError::new(
+      self.name(),
+      &format!("{} problem(s) found", diagnostics.len()),
+    );
+    e.diagnostics = diagnostics;
+    Err(e)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ConflictPolicy, ValidateTask};
+  use crate::event::RenameEvent;
+  use crate::task::Task;
+  use crate::tasks::context::{RenameContext, RenameEntry};
+  use crate::validity::Platform;
+  use std::{cell::RefCell, fs, path::Path, rc::Rc};
+
+  fn entry(src: &str, dest: &str) -> RenameEntry {
+    let mut e = RenameEntry::new(Path::new(src));
+    e.dest = Some(Path::new(dest).to_path_buf());
+    e
+  }
+
+  #[test]
+  fn test_validate_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_b"));
+    // renamed to the source of another entry
+    context
+      .entries
+      .push(entry("test_validate_c", "test_validate_a"));
+
+    assert!(ValidateTask::new().execute(&mut context).is_ok());
+  }
+
+  #[test]
+  fn test_validate_duplicated_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_x"));
+    context
+      .entries
+      .push(entry("test_validate_b", "test_validate_x"));
+    context
+      .entries
+      .push(RenameEntry::new(Path::new("test_validate_c")));
+
+    let e = ValidateTask::new().execute(&mut context).unwrap_err();
+    assert_eq!("validate", e.task);
+    assert_eq!(2, e.diagnostics.len());
+  }
+
+  #[test]
+  fn test_validate_normalization_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    // "ガ" composed and decomposed
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_\u{30AC}"));
+    context
+      .entries
+      .push(entry("test_validate_b", "test_validate_\u{30AB}\u{3099}"));
+
+    let e = ValidateTask::new().execute(&mut context).unwrap_err();
+    assert_eq!(1, e.diagnostics.len());
+    assert!(e.diagnostics[0].ends_with("same name after Unicode normalization"));
+  }
+
+  #[test]
+  fn test_validate_exists_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context.entries.push(entry("test_validate_a", "README.md"));
+
+    let e = ValidateTask::new().execute(&mut context).unwrap_err();
+    assert_eq!(1, e.diagnostics.len());
+    assert!(e.diagnostics[0].ends_with("already exists"));
+  }
+
+  #[test]
+  fn test_validate_events_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_x"));
+    context
+      .entries
+      .push(entry("test_validate_b", "test_validate_x"));
+
+    let codes = Rc::new(RefCell::new(vec![]));
+    let c = codes.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      if let RenameEvent::Conflict { code, .. } = event {
+        c.borrow_mut().push(code.clone());
+      }
+    }));
+
+    assert!(ValidateTask::new().execute(&mut context).is_err());
+    assert_eq!(vec!["duplicated", "duplicated"], *codes.borrow());
+  }
+
+  #[test]
+  fn test_validate_outside_root_1() {
+    let mut context = RenameContext::new(Path::new("test_validate_root"));
+    context
+      .entries
+      .push(entry("test_validate_root/a", "test_validate_root/2024/a"));
+    context
+      .entries
+      .push(entry("test_validate_root/b", "test_validate_b"));
+
+    let e = ValidateTask::new().execute(&mut context).unwrap_err();
+    assert_eq!(1, e.diagnostics.len());
+    assert!(e.diagnostics[0].starts_with("test_validate_root/b"));
+  }
+
+  #[test]
+  fn test_validate_case_insensitive_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_X"));
+    context
+      .entries
+      .push(entry("test_validate_b", "test_validate_x"));
+
+    assert!(ValidateTask::new().execute(&mut context).is_ok());
+
+    let task = ValidateTask::new().with_case_insensitive(true);
+    let e = task.execute(&mut context).unwrap_err();
+    assert!(e.diagnostics[0].ends_with("duplicated"));
+  }
+
+  #[test]
+  fn test_validate_case_insensitive_2() {
+    let mut context = RenameContext::new(Path::new("."));
+    // "README.md" exists
+    context.entries.push(entry("test_validate_a", "readme.md"));
+
+    let task = ValidateTask::new().with_case_insensitive(true);
+    let e = task.execute(&mut context).unwrap_err();
+    assert!(e.diagnostics[0].ends_with("already exists"));
+  }
+
+  #[test]
+  fn test_validate_case_only_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    // renamed to itself on case-insensitive file systems
+    context.entries.push(entry("README.md", "readme.md"));
+
+    let task = ValidateTask::new().with_case_insensitive(true);
+    assert!(task.execute(&mut context).is_ok());
+  }
+
+  #[test]
+  fn test_validate_platform_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    context
+      .entries
+      .push(entry("test_validate_a", "test_validate_a:1"));
+    context
+      .entries
+      .push(entry("test_validate_b", "test_validate_con/NUL.txt"));
+
+    assert!(ValidateTask::new().execute(&mut context).is_ok());
+
+    let task = ValidateTask::new().with_platform(Some(Platform::Windows));
+    let e = task.execute(&mut context).unwrap_err();
+    assert_eq!(2, e.diagnostics.len());
+    assert!(e.diagnostics[0].ends_with("invalid character ':'"));
+    assert!(e.diagnostics[1].ends_with("reserved name \"NUL\""));
+  }
+
+  #[test]
+  fn test_validate_too_long_1() {
+    let mut context = RenameContext::new(Path::new("."));
+    let dest = format!("test_validate_{}", "x".repeat(255));
+    context.entries.push(entry("test_validate_a", &dest));
+
+    let codes = Rc::new(RefCell::new(vec![]));
+    let c = codes.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      if let RenameEvent::Conflict { code, .. } = event {
+        c.borrow_mut().push(code.clone());
+      }
+    }));
+
+    let task = ValidateTask::new().with_platform(Some(Platform::Linux));
+    assert!(task.execute(&mut context).is_err());
+    assert_eq!(vec!["name_too_long"], *codes.borrow());
+  }
+
+  #[test]
+  fn test_validate_skip_1() {
+    let dir = Path::new("test_validate_skip_1");
+    fs::create_dir_all(dir).unwrap();
+    for name in ["a", "b", "c", "d"] {
+      fs::write(dir.join(name), name).unwrap();
+    }
+    let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+    let mut context = RenameContext::new(dir);
+    context.entries.push(entry(&path("a"), &path("x")));
+    context.entries.push(entry(&path("b"), &path("x")));
+    // "a" stays when it is skipped
+    context.entries.push(entry(&path("c"), &path("a")));
+    context.entries.push(entry(&path("d"), &path("e")));
+
+    let skipped = Rc::new(RefCell::new(vec![]));
+    let s = skipped.clone();
+    context.events = Some(Box::new(move |event: &RenameEvent| {
+      if let RenameEvent::Skipped { path, .. } = event {
+        s.borrow_mut().push(path.clone());
+      }
+    }));
+
+    let task = ValidateTask::new().with_conflict_policy(ConflictPolicy::Skip);
+    let r = task.execute(&mut context);
+    fs::remove_dir_all(dir).unwrap();
+
+    assert!(r.is_ok());
+    assert_eq!(1, context.entries.len());
+    assert_eq!(dir.join("d"), context.entries[0].src);
+    assert_eq!(
+      vec![dir.join("a"), dir.join("b"), dir.join("c")],
+      *skipped.borrow()
+    );
+  }
+}
@@ -1,8 +1,12 @@
-use task;
+use crate::task::{Task, TaskError};
 
-struct VersionInfoTask;
-impl Task<Target> for VersionInfoTask {
-  fn execute(&self, target: &mut Target) -> bool {
+pub struct VersionInfoTask;
+impl<T> Task<T> for VersionInfoTask {
+  fn name(&self) -> &str {
+    "version"
+  }
+
+  fn execute(&self, _target: &mut T) -> Result<(), TaskError> {
     println!("N E K O  N O");
     println!("    H I T A I  D E");
     println!("      A S O B U");
@@ -22,6 +26,6 @@ impl Task<Target> for VersionInfoTask {
     println!("  |`. |  |--| |---");
     println!("  |  `|  |  | |___");
 
-    true
+    Ok(())
   }
 }
@@ -0,0 +1,454 @@
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+pub struct Transliterate {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transliteration {
+  /// Katakana to hiragana
+  Hiragana,
+  /// Hiragana to katakana
+  Katakana,
+  /// Kana to Hepburn romaji
+  Romaji,
+  /// Accented Latin to ASCII
+  Ascii,
+}
+
+impl Transliteration {
+  ///
+  /// Find a transliteration by its modifier name.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::transliterate::Transliteration;
+  ///
+  /// assert_eq!(Transliteration::from_name("romaji"), Some(Transliteration::Romaji));
+  /// assert_eq!(Transliteration::from_name("unknown"), None);
+  /// ```
+  ///
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "hiragana" => Some(Transliteration::Hiragana),
+      "katakana" => Some(Transliteration::Katakana),
+      "romaji" => Some(Transliteration::Romaji),
+      "ascii" => Some(Transliteration::Ascii),
+      _ => None,
+    }
+  }
+}
+
+/// distance between hiragana and katakana code points
+const KANA_OFFSET: u32 = 0x60;
+
+impl Transliterate {
+  ///
+  /// Apply the specified transliteration.
+  ///
+  pub fn apply(s: &str, transliteration: Transliteration) -> String {
+    match transliteration {
+      Transliteration::Hiragana => Transliterate::katakana_to_hiragana(s),
+      Transliteration::Katakana => Transliterate::hiragana_to_katakana(s),
+      Transliteration::Romaji => Transliterate::kana_to_romaji(s),
+      Transliteration::Ascii => Transliterate::ascii_fold(s),
+    }
+  }
+
+  ///
+  /// Convert hiragana to katakana.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::transliterate::Transliterate;
+  ///
+  /// assert_eq!(Transliterate::hiragana_to_katakana("ねこ.txt"), "ネコ.txt");
+  /// ```
+  ///
+  pub fn hiragana_to_katakana(s: &str) -> String {
+    s.chars()
+      .map(|c| match c {
+        // ぁ..ゖ, ゝ, ゞ
+        '\u{3041}'..='\u{3096}' | '\u{309D}' | '\u{309E}' => {
+          char::from_u32(c as u32 + KANA_OFFSET).unwrap()
+        }
+        _ => c,
+      })
+      .collect()
+  }
+
+  ///
+  /// Convert katakana to hiragana.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::transliterate::Transliterate;
+  ///
+  /// assert_eq!(Transliterate::katakana_to_hiragana("ネコ.txt"), "ねこ.txt");
+  /// ```
+  ///
+  pub fn katakana_to_hiragana(s: &str) -> String {
+    s.chars()
+      .map(|c| match c {
+        // ァ..ヶ, ヽ, ヾ
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}' | '\u{30FE}' => {
+          char::from_u32(c as u32 - KANA_OFFSET).unwrap()
+        }
+        _ => c,
+      })
+      .collect()
+  }
+
+  ///
+  /// Transliterate hiragana and katakana to Hepburn romaji.
+  /// Characters other than kana (e.g. kanji) are left as they are.
+  ///
+  /// `ん` is always written as `n`, `っ` doubles the following consonant,
+  /// and `ー` repeats the preceding vowel.
+  /// Decomposed (NFD) and half-width kana are composed first, e.g. "ｶﾞ" is read as "ガ".
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::transliterate::Transliterate;
+  ///
+  /// assert_eq!(Transliterate::kana_to_romaji("きょうと"), "kyouto");
+  /// assert_eq!(Transliterate::kana_to_romaji("マッチ"), "matchi");
+  /// ```
+  ///
+  pub fn kana_to_romaji(s: &str) -> String {
+    let composed = Transliterate::compose_kana(s);
+    let chars: Vec<char> = Transliterate::katakana_to_hiragana(&composed)
+      .chars()
+      .collect();
+    let mut ret = String::new();
+    let mut sokuon = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+      let c = chars[i];
+      i += 1;
+
+      if c == 'っ' {
+        sokuon = true;
+        continue;
+      }
+
+      if c == 'ー' {
+        if let Some(v) = ret.chars().last().filter(|v| "aeiou".contains(*v)) {
+          ret.push(v);
+        }
+        continue;
+      }
+
+      let base = match Transliterate::romaji_of(c) {
+        Some(r) => r,
+        None => {
+          sokuon = false;
+          ret.push(c);
+          continue;
+        }
+      };
+
+      let mut syllable = base.to_string();
+      if let Some(&next) = chars.get(i) {
+        if let Some(v) = Transliterate::small_yayuyo(next).filter(|_| base.len() > 1) {
+          if base.ends_with('i') {
+            // きゃ -> kya, しゃ -> sha
+            syllable.pop();
+            if !matches!(base, "shi" | "chi" | "ji") {
+              syllable.push('y');
+            }
+            syllable.push_str(v);
+            i += 1;
+          }
+        } else if let Some(v) = Transliterate::small_vowel(next) {
+          // ふぁ -> fa, てぃ -> ti, うぃ -> wi, いぇ -> ye
+          syllable = match base {
+            "u" => format!("w{}", v),
+            "i" if v == 'e' => "ye".to_string(),
+            _ if base.len() > 1 => format!("{}{}", &base[..base.len() - 1], v),
+            _ => format!("{}{}", base, v),
+          };
+          i += 1;
+        }
+      }
+
+      if sokuon {
+        sokuon = false;
+        let first = syllable.chars().next().unwrap();
+        if !"aeioun".contains(first) {
+          // っち -> tchi
+          ret.push(if syllable.starts_with("ch") {
+            't'
+          } else {
+            first
+          });
+        }
+      }
+
+      ret.push_str(&syllable);
+    }
+
+    ret
+  }
+
+  /// NFC, with half-width katakana (U+FF61..U+FF9F) mapped to full-width
+  fn compose_kana(s: &str) -> String {
+    let mut ret = String::new();
+    for c in s.chars() {
+      match c {
+        '\u{FF61}'..='\u{FF9F}' => ret.extend(c.to_string().nfkc()),
+        _ => ret.push(c),
+      }
+    }
+    ret.nfc().collect()
+  }
+
+  ///
+  /// Fold accented Latin characters to ASCII.
+  /// Characters which have no ASCII equivalent are left as they are.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::transliterate::Transliterate;
+  ///
+  /// assert_eq!(Transliterate::ascii_fold("Café Straße.txt"), "Cafe Strasse.txt");
+  /// ```
+  ///
+  pub fn ascii_fold(s: &str) -> String {
+    let mut ret = String::new();
+
+    for c in s.chars() {
+      if c.is_ascii() {
+        ret.push(c);
+        continue;
+      }
+
+      // letters without a decomposition
+      let special = match c {
+        'ß' => Some("ss"),
+        'æ' => Some("ae"),
+        'Æ' => Some("AE"),
+        'œ' => Some("oe"),
+        'Œ' => Some("OE"),
+        'ø' => Some("o"),
+        'Ø' => Some("O"),
+        'đ' | 'ð' => Some("d"),
+        'Đ' | 'Ð' => Some("D"),
+        'ł' => Some("l"),
+        'Ł' => Some("L"),
+        'þ' => Some("th"),
+        'Þ' => Some("Th"),
+        'ı' => Some("i"),
+        _ => None,
+      };
+      if let Some(r) = special {
+        ret.push_str(r);
+        continue;
+      }
+
+      // é -> e + U+0301 -> e
+      let folded: String = c.nfkd().filter(|d| !is_combining_mark(*d)).collect();
+      if !folded.is_empty() && folded.is_ascii() {
+        ret.push_str(&folded);
+      } else {
+        ret.push(c);
+      }
+    }
+
+    ret
+  }
+
+  fn romaji_of(c: char) -> Option<&'static str> {
+    let r = match c {
+      'あ' | 'ぁ' => "a",
+      'い' | 'ぃ' | 'ゐ' => "i",
+      'う' | 'ぅ' => "u",
+      'え' | 'ぇ' | 'ゑ' => "e",
+      'お' | 'ぉ' | 'を' => "o",
+      'か' | 'ゕ' => "ka",
+      'き' => "ki",
+      'く' => "ku",
+      'け' | 'ゖ' => "ke",
+      'こ' => "ko",
+      'さ' => "sa",
+      'し' => "shi",
+      'す' => "su",
+      'せ' => "se",
+      'そ' => "so",
+      'た' => "ta",
+      'ち' => "chi",
+      'つ' => "tsu",
+      'て' => "te",
+      'と' => "to",
+      'な' => "na",
+      'に' => "ni",
+      'ぬ' => "nu",
+      'ね' => "ne",
+      'の' => "no",
+      'は' => "ha",
+      'ひ' => "hi",
+      'ふ' => "fu",
+      'へ' => "he",
+      'ほ' => "ho",
+      'ま' => "ma",
+      'み' => "mi",
+      'む' => "mu",
+      'め' => "me",
+      'も' => "mo",
+      'や' | 'ゃ' => "ya",
+      'ゆ' | 'ゅ' => "yu",
+      'よ' | 'ょ' => "yo",
+      'ら' => "ra",
+      'り' => "ri",
+      'る' => "ru",
+      'れ' => "re",
+      'ろ' => "ro",
+      'わ' | 'ゎ' => "wa",
+      'ん' => "n",
+      'が' => "ga",
+      'ぎ' => "gi",
+      'ぐ' => "gu",
+      'げ' => "ge",
+      'ご' => "go",
+      'ざ' => "za",
+      'じ' | 'ぢ' => "ji",
+      'ず' | 'づ' => "zu",
+      'ぜ' => "ze",
+      'ぞ' => "zo",
+      'だ' => "da",
+      'で' => "de",
+      'ど' => "do",
+      'ば' => "ba",
+      'び' => "bi",
+      'ぶ' => "bu",
+      'べ' => "be",
+      'ぼ' => "bo",
+      'ぱ' => "pa",
+      'ぴ' => "pi",
+      'ぷ' => "pu",
+      'ぺ' => "pe",
+      'ぽ' => "po",
+      'ゔ' => "vu",
+      // katakana only
+      'ヷ' => "va",
+      'ヸ' => "vi",
+      'ヹ' => "ve",
+      'ヺ' => "vo",
+      _ => return None,
+    };
+
+    Some(r)
+  }
+
+  fn small_yayuyo(c: char) -> Option<&'static str> {
+    match c {
+      'ゃ' => Some("a"),
+      'ゅ' => Some("u"),
+      'ょ' => Some("o"),
+      _ => None,
+    }
+  }
+
+  fn small_vowel(c: char) -> Option<char> {
+    match c {
+      'ぁ' => Some('a'),
+      'ぃ' => Some('i'),
+      'ぅ' => Some('u'),
+      'ぇ' => Some('e'),
+      'ぉ' => Some('o'),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Transliterate, Transliteration};
+
+  #[test]
+  fn test_hiragana_to_katakana_1() {
+    let s = "ひらがな と カタカナ ゝゞ";
+    let r = Transliterate::hiragana_to_katakana(s);
+    assert_eq!(String::from("ヒラガナ ト カタカナ ヽヾ"), r);
+  }
+
+  #[test]
+  fn test_katakana_to_hiragana_1() {
+    let s = "カタカナ と ひらがな ヴ";
+    let r = Transliterate::katakana_to_hiragana(s);
+    assert_eq!(String::from("かたかな と ひらがな ゔ"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_1() {
+    let s = "しんじゅく";
+    let r = Transliterate::kana_to_romaji(s);
+    assert_eq!(String::from("shinjuku"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_2() {
+    let s = "チョコレート";
+    let r = Transliterate::kana_to_romaji(s);
+    assert_eq!(String::from("chokoreeto"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_3() {
+    let s = "がっこう_まっちゃ";
+    let r = Transliterate::kana_to_romaji(s);
+    assert_eq!(String::from("gakkou_matcha"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_4() {
+    let s = "ファイル_ティー_ウィンドウ";
+    let r = Transliterate::kana_to_romaji(s);
+    assert_eq!(String::from("fairu_tii_windou"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_5() {
+    let s = "第１話_ねこ.mp4";
+    let r = Transliterate::kana_to_romaji(s);
+    assert_eq!(String::from("第１話_neko.mp4"), r);
+  }
+
+  #[test]
+  fn test_kana_to_romaji_composed_1() {
+    // decomposed, as in names from macOS
+    let r = Transliterate::kana_to_romaji("\u{30AB}\u{3099}\u{30E0}.txt");
+    assert_eq!(String::from("gamu.txt"), r);
+    // half-width katakana, and their voiced sound mark
+    let r = Transliterate::kana_to_romaji(
+      "\u{FF76}\u{FF80}\u{FF76}\u{FF85}_\u{FF8C}\u{FF9E}\u{FF70}\u{FF9D}",
+    );
+    assert_eq!(String::from("katakana_buun"), r);
+  }
+
+  #[test]
+  fn test_ascii_fold_1() {
+    let s = "Crème Brûlée à la Ærø_Łódź.txt";
+    let r = Transliterate::ascii_fold(s);
+    assert_eq!(String::from("Creme Brulee a la AEro_Lodz.txt"), r);
+  }
+
+  #[test]
+  fn test_ascii_fold_2() {
+    let s = "ねこ_ｆｕｌｌ.txt";
+    let r = Transliterate::ascii_fold(s);
+    assert_eq!(String::from("ねこ_full.txt"), r);
+  }
+
+  #[test]
+  fn test_apply_1() {
+    let s = "ねこ";
+    assert_eq!(
+      String::from("ネコ"),
+      Transliterate::apply(s, Transliteration::Katakana)
+    );
+    assert_eq!(
+      String::from("neko"),
+      Transliterate::apply(s, Transliteration::Romaji)
+    );
+  }
+}
@@ -0,0 +1,140 @@
+use clap::ValueEnum;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extension::Extension;
+use crate::validity::Platform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TruncatePolicy {
+  /// cut the end of the name
+  Cut,
+  /// cut the end of the name and append a hash of the whole name, to keep names unique
+  Hash,
+}
+
+pub struct Truncate {}
+
+impl Truncate {
+  ///
+  /// Shorten a name to `max` in the unit of the platform, keeping the extension.
+  /// The name is cut between grapheme clusters, so accents and emoji are not split.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::truncate::{Truncate, TruncatePolicy};
+  /// use nae::validity::Platform;
+  ///
+  /// assert_eq!(Truncate::fit("long title.mp4", 10, Platform::Linux, TruncatePolicy::Cut), "long t.mp4");
+  /// assert_eq!(
+  ///   Truncate::fit("a long video title.mp4", 16, Platform::Linux, TruncatePolicy::Hash),
+  ///   "a l~55ea2126.mp4"
+  /// );
+  /// ```
+  ///
+  pub fn fit(name: &str, max: usize, platform: Platform, policy: TruncatePolicy) -> String {
+    if platform.length(name) <= max {
+      return name.to_string();
+    }
+
+    let (stem, ext) = Extension::split(name);
+    let suffix = match policy {
+      TruncatePolicy::Cut => String::new(),
+      TruncatePolicy::Hash => format!("~{:08x}", Truncate::hash(name)),
+    };
+
+    let reserved = platform.length(ext) + platform.length(&suffix);
+    if reserved >= max {
+      // no room for the name, keep the beginning of the whole name
+      return Truncate::prefix(name, max, platform).to_string();
+    }
+
+    let stem = Truncate::prefix(stem, max - reserved, platform);
+    format!("{}{}{}", stem, suffix, ext)
+  }
+
+  ///
+  /// Longest prefix of `s` not longer than `max`, ending at a grapheme cluster boundary.
+  ///
+  pub fn prefix(s: &str, max: usize, platform: Platform) -> &str {
+    let mut length = 0;
+    let mut end = 0;
+
+    for (i, g) in s.grapheme_indices(true) {
+      length += platform.length(g);
+      if length > max {
+        break;
+      }
+      end = i + g.len();
+    }
+
+    &s[..end]
+  }
+
+  /// FNV-1a, stable across builds unlike `DefaultHasher`
+  fn hash(s: &str) -> u32 {
+    s.bytes()
+      .fold(0x811c9dc5, |h, b| (h ^ b as u32).wrapping_mul(0x01000193))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Truncate, TruncatePolicy};
+  use crate::validity::Platform;
+
+  #[test]
+  fn test_fit_1() {
+    let p = TruncatePolicy::Cut;
+    assert_eq!("a.txt", Truncate::fit("a.txt", 10, Platform::Linux, p));
+    assert_eq!(
+      "abc.txt",
+      Truncate::fit("abcdef.txt", 7, Platform::Linux, p)
+    );
+    // 3 bytes each
+    assert_eq!(
+      "ねこ.txt",
+      Truncate::fit("ねこです.txt", 11, Platform::Linux, p)
+    );
+    assert_eq!(
+      "ねこで.txt",
+      Truncate::fit("ねこです.txt", 7, Platform::Windows, p)
+    );
+    // not an extension
+    assert_eq!(
+      "Vol. 1 T",
+      Truncate::fit("Vol. 1 The Beginning of Everything", 8, Platform::Linux, p)
+    );
+  }
+
+  #[test]
+  fn test_fit_grapheme_1() {
+    let p = TruncatePolicy::Cut;
+    // "e" + combining acute accent
+    assert_eq!(
+      "ab.txt",
+      Truncate::fit("abe\u{301}.txt", 8, Platform::Linux, p)
+    );
+    // a ZWJ sequence is kept whole or dropped
+    assert_eq!("a.txt", Truncate::fit("a👨‍👩‍👧.txt", 20, Platform::Linux, p));
+  }
+
+  #[test]
+  fn test_fit_hash_1() {
+    let p = TruncatePolicy::Hash;
+    let a = Truncate::fit(
+      &format!("{}a.mp4", "x".repeat(300)),
+      255,
+      Platform::Linux,
+      p,
+    );
+    let b = Truncate::fit(
+      &format!("{}b.mp4", "x".repeat(300)),
+      255,
+      Platform::Linux,
+      p,
+    );
+    assert_eq!(255, a.len());
+    assert!(a.ends_with(".mp4"));
+    assert_ne!(a, b);
+  }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+use crate::truncate::{Truncate, TruncatePolicy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Platform {
+  Linux,
+  Windows,
+  #[value(name = "macos")]
+  MacOs,
+  /// POSIX portable filename character set
+  Portable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+  Empty,
+  /// e.g. "CON" on Windows, "." and ".."
+  ReservedName(String),
+  InvalidCharacter(char),
+  /// not allowed on Windows
+  TrailingDotOrSpace,
+  /// starts with "-", not allowed in portable names
+  LeadingHyphen,
+  /// length in the unit of the platform (bytes or UTF-16 units)
+  TooLong {
+    length: usize,
+    limit: usize,
+  },
+}
+
+impl fmt::Display for Violation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Violation::Empty => write!(f, "empty name"),
+      Violation::ReservedName(name) => write!(f, "reserved name \"{}\"", name),
+      Violation::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+      Violation::TrailingDotOrSpace => write!(f, "trailing dot or space"),
+      Violation::LeadingHyphen => write!(f, "leading hyphen"),
+      Violation::TooLong { length, limit } => write!(f, "too long ({} > {})", length, limit),
+    }
+  }
+}
+
+/// maximum length of a file name, in the unit of `Platform::length()`
+pub const NAME_MAX: usize = 255;
+
+const WINDOWS_RESERVED: [&str; 22] = [
+  "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+  "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+impl Platform {
+  ///
+  /// Platform this program runs on.
+  ///
+  pub fn current() -> Self {
+    if cfg!(target_os = "windows") {
+      Platform::Windows
+    } else if cfg!(target_os = "macos") {
+      Platform::MacOs
+    } else {
+      Platform::Linux
+    }
+  }
+
+  ///
+  /// Platform from a name, e.g. `Plan`'s platform string.
+  ///
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.to_lowercase().as_str() {
+      "linux" => Some(Platform::Linux),
+      "windows" => Some(Platform::Windows),
+      "macos" => Some(Platform::MacOs),
+      "portable" => Some(Platform::Portable),
+      _ => None,
+    }
+  }
+
+  fn is_invalid_char(&self, c: char) -> bool {
+    match self {
+      Platform::Linux => c == '/' || c == '\0',
+      Platform::Windows => "<>:\"/\\|?*".contains(c) || (c as u32) < 0x20,
+      Platform::MacOs => c == '/' || c == ':' || c == '\0',
+      Platform::Portable => !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-'),
+    }
+  }
+
+  ///
+  /// Length of a name as counted by the file systems of the platform,
+  /// UTF-16 code units on Windows and macOS, bytes otherwise.
+  ///
+  pub fn length(&self, name: &str) -> usize {
+    match self {
+      Platform::Windows | Platform::MacOs => name.encode_utf16().count(),
+      Platform::Linux | Platform::Portable => name.len(),
+    }
+  }
+}
+
+pub struct Validity {}
+
+impl Validity {
+  ///
+  /// Check a file name (not a path) against the rules of the platform.
+  ///
+  /// # Return
+  /// * `Vec<Violation>` - every rule broken, empty if the name is valid
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::validity::{Platform, Validity, Violation};
+  ///
+  /// assert!(Validity::check("report.txt", Platform::Windows).is_empty());
+  /// assert_eq!(
+  ///   Validity::check("con.txt", Platform::Windows),
+  ///   vec![Violation::ReservedName("con".to_string())]
+  /// );
+  /// ```
+  ///
+  pub fn check(name: &str, platform: Platform) -> Vec<Violation> {
+    let mut ret = vec![];
+
+    if name.is_empty() {
+      ret.push(Violation::Empty);
+      return ret;
+    }
+
+    if name == "." || name == ".." {
+      ret.push(Violation::ReservedName(name.to_string()));
+    }
+
+    let mut invalid: Vec<char> = name
+      .chars()
+      .filter(|c| platform.is_invalid_char(*c))
+      .collect();
+    invalid.dedup();
+    for c in invalid {
+      ret.push(Violation::InvalidCharacter(c));
+    }
+
+    match platform {
+      Platform::Windows => {
+        if let Some(reserved) = Validity::windows_reserved(name) {
+          ret.push(Violation::ReservedName(reserved));
+        }
+        if name.ends_with('.') || name.ends_with(' ') {
+          ret.push(Violation::TrailingDotOrSpace);
+        }
+      }
+      Platform::Portable if name.starts_with('-') => ret.push(Violation::LeadingHyphen),
+      _ => {}
+    }
+
+    let length = platform.length(name);
+    if length > NAME_MAX {
+      ret.push(Violation::TooLong {
+        length,
+        limit: NAME_MAX,
+      });
+    }
+
+    ret
+  }
+
+  pub fn is_valid(name: &str, platform: Platform) -> bool {
+    Validity::check(name, platform).is_empty()
+  }
+
+  ///
+  /// Make a file name valid on the platform.
+  /// Invalid characters are replaced, long names are shortened keeping the extension,
+  /// trailing dots and spaces are removed, and reserved names get `replacement` appended.
+  ///
+  /// # Examples
+  /// ```
+  /// use nae::validity::{Platform, Validity};
+  ///
+  /// assert_eq!(Validity::sanitize("a:b?.txt", Platform::Windows, '_'), "a_b_.txt");
+  /// assert_eq!(Validity::sanitize("NUL.log", Platform::Windows, '_'), "NUL_.log");
+  /// assert_eq!(Validity::sanitize("draft. ", Platform::Windows, '_'), "draft");
+  /// ```
+  ///
+  pub fn sanitize(name: &str, platform: Platform, replacement: char) -> String {
+    let replacement = if platform.is_invalid_char(replacement) {
+      '_'
+    } else {
+      replacement
+    };
+
+    let mut ret: String = name
+      .chars()
+      .map(|c| {
+        if platform.is_invalid_char(c) {
+          replacement
+        } else {
+          c
+        }
+      })
+      .collect();
+    // before trimming, the cut may end with a dot or space
+    ret = Truncate::fit(&ret, NAME_MAX, platform, TruncatePolicy::Cut);
+
+    match platform {
+      Platform::Windows => {
+        ret = ret.trim_end_matches(['.', ' ']).to_string();
+        // right after the part checked by `windows_reserved()`, e.g. "NUL_.tar.gz"
+        if Validity::windows_reserved(&ret).is_some() {
+          let i = ret.find('.').unwrap_or(ret.len());
+          ret.insert(i, replacement);
+        }
+      }
+      Platform::Portable if ret.starts_with('-') => ret.replace_range(0..1, "_"),
+      _ => {}
+    }
+
+    if ret.is_empty() {
+      ret.push(replacement);
+    } else if ret == "." || ret == ".." {
+      ret = ret.replace('.', &replacement.to_string());
+    }
+
+    ret
+  }
+
+  /// reserved device name, with or without an extension (e.g. "con.txt")
+  fn windows_reserved(name: &str) -> Option<String> {
+    let stem = name.split('.').next().unwrap_or(name);
+    let stem = stem.trim_end_matches(' ');
+
+    if WINDOWS_RESERVED
+      .iter()
+      .any(|r| r.eq_ignore_ascii_case(stem))
+    {
+      Some(stem.to_string())
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Platform, Validity, Violation};
+
+  #[test]
+  fn test_check_linux_1() {
+    assert!(Validity::is_valid("a:b?.txt", Platform::Linux));
+    assert_eq!(
+      vec![Violation::InvalidCharacter('/')],
+      Validity::check("a/b", Platform::Linux)
+    );
+    assert_eq!(
+      vec![Violation::ReservedName("..".to_string())],
+      Validity::check("..", Platform::Linux)
+    );
+    assert_eq!(vec![Violation::Empty], Validity::check("", Platform::Linux));
+  }
+
+  #[test]
+  fn test_check_windows_1() {
+    assert_eq!(
+      vec![
+        Violation::InvalidCharacter(':'),
+        Violation::InvalidCharacter('?')
+      ],
+      Validity::check("a:b?.txt", Platform::Windows)
+    );
+    assert_eq!(
+      vec![Violation::ReservedName("LPT1".to_string())],
+      Validity::check("LPT1", Platform::Windows)
+    );
+    assert!(Validity::is_valid("CONSOLE.txt", Platform::Windows));
+    assert_eq!(
+      vec![Violation::TrailingDotOrSpace],
+      Validity::check("a.", Platform::Windows)
+    );
+  }
+
+  #[test]
+  fn test_check_length_1() {
+    // 100 characters, 300 bytes in UTF-8
+    let name = "あ".repeat(100);
+    assert!(Validity::is_valid(&name, Platform::Windows));
+    assert_eq!(
+      vec![Violation::TooLong {
+        length: 300,
+        limit: 255
+      }],
+      Validity::check(&name, Platform::Linux)
+    );
+  }
+
+  #[test]
+  fn test_check_portable_1() {
+    assert!(Validity::is_valid("IMG_0001.jpg", Platform::Portable));
+    assert_eq!(
+      vec![Violation::InvalidCharacter(' '), Violation::LeadingHyphen],
+      Validity::check("-a b", Platform::Portable)
+    );
+  }
+
+  #[test]
+  fn test_sanitize_1() {
+    assert_eq!(
+      "con_.txt",
+      Validity::sanitize("con.txt", Platform::Windows, '_')
+    );
+    assert_eq!(
+      "a-b.txt",
+      Validity::sanitize("a:b.txt", Platform::MacOs, '-')
+    );
+    assert_eq!(
+      "_a_b.txt",
+      Validity::sanitize("-a b.txt", Platform::Portable, '_')
+    );
+    // the replacement itself is invalid
+    assert_eq!("a_b", Validity::sanitize("a/b", Platform::Linux, '/'));
+    assert_eq!("__", Validity::sanitize("..", Platform::Linux, '_'));
+  }
+
+  #[test]
+  fn test_sanitize_reserved_1() {
+    assert_eq!(
+      "NUL_.tar.gz",
+      Validity::sanitize("NUL.tar.gz", Platform::Windows, '_')
+    );
+    for name in ["CON.tar.gz", "com1.a.b", "aux", "PRN .txt"] {
+      let sanitized = Validity::sanitize(name, Platform::Windows, '_');
+      assert!(
+        Validity::is_valid(&sanitized, Platform::Windows),
+        "{}",
+        sanitized
+      );
+    }
+  }
+
+  #[test]
+  fn test_sanitize_length_1() {
+    let name = format!("{}.txt", "あ".repeat(100));
+    let r = Validity::sanitize(&name, Platform::Linux, '_');
+    assert!(Validity::is_valid(&r, Platform::Linux));
+    assert!(r.ends_with("あ.txt"));
+    // 83 * 3 + 4 bytes
+    assert_eq!(83, r.chars().count() - 4);
+
+    // no extension, cut right after the dot
+    let name = format!("{}. {}", "a".repeat(254), "b".repeat(20));
+    let r = Validity::sanitize(&name, Platform::Windows, '_');
+    assert_eq!("a".repeat(254), r);
+  }
+}